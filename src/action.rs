@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+
+use crate::config::Action;
+use crate::repolist::Repo;
+use crate::{execute, quote, shell_command, SlipError};
+
+/// Command line of `action` for the checkout at `location` of `repo`, with
+/// `{path}`, `{name}` and `{url}` replaced by shell quoted values. The command is
+/// read once, so placeholders inside the values stay as they are.
+pub fn command_line(action: &Action, repo: &Repo, location: &str) -> String {
+    let values = [
+        ("{path}", location),
+        ("{name}", repo.name.as_str()),
+        ("{url}", repo.url.as_str()),
+    ];
+    let mut line = String::with_capacity(action.command.len());
+    let mut rest = action.command.as_str();
+    while let Some(start) = rest.find('{') {
+        line.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let placeholder = values
+            .iter()
+            .find_map(|(placeholder, value)| Some((value, rest.strip_prefix(placeholder)?)));
+        match placeholder {
+            Some((value, after)) => {
+                line.push_str(&shell_quote(value));
+                rest = after;
+            }
+            None => {
+                line.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    line.push_str(rest);
+    line
+}
+
+fn shell_quote(value: &str) -> Cow<'_, str> {
+    if cfg!(target_os = "windows") {
+        Cow::Owned(cmd_quote(value))
+    } else {
+        quote(value)
+    }
+}
+
+/// Quotes `value` for `cmd /C`: `"` is doubled, and `%` is left outside the quotes and
+/// escaped so that `%VAR%` isn't expanded.
+fn cmd_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\"").replace('%', "\"^%\""))
+}
+
+/// Runs `action` inside `location`. Editors and shells are interactive, so how
+/// they exit is not an error.
+pub fn run_action(action: &Action, repo: &Repo, location: &str) -> Result<(), SlipError> {
+    let command = command_line(action, repo, location);
+    tracing::info!(action = %action.name, %command, location, "launch");
+    let (shell, args) = shell_command(&command);
+    execute(shell, &args, Some(location))?;
+    Ok(())
+}
+
+#[test]
+fn quote_placeholders() {
+    let action = Action {
+        name: "editor".to_string(),
+        command: "nvim {path} # {name} from {url}".to_string(),
+        key: None,
+    };
+    let repo = Repo {
+        url: "git@github.com:acme/api.git".to_string(),
+        ..Repo::for_test("it's api", "/src/acme api")
+    };
+    assert_eq!(
+        r"nvim '/src/acme api' # 'it'\''s api' from git@github.com:acme/api.git",
+        command_line(&action, &repo, &repo.location)
+    );
+    // placeholders inside the values are left alone
+    let repo = Repo {
+        url: "https://example.com/{path}".to_string(),
+        ..Repo::for_test("api", "/src/{name}")
+    };
+    assert_eq!(
+        "nvim '/src/{name}' # api from 'https://example.com/{path}'",
+        command_line(&action, &repo, &repo.location)
+    );
+}
+
+#[test]
+fn quote_for_cmd() {
+    assert_eq!(r#""C:\src\acme api""#, cmd_quote(r"C:\src\acme api"));
+    assert_eq!(r#""say ""hi"" & exit""#, cmd_quote(r#"say "hi" & exit"#));
+    assert_eq!(r#""100"^%"PATH"^%"""#, cmd_quote("100%PATH%"));
+}
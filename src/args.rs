@@ -1,6 +1,8 @@
 use clap::ArgGroup;
 use clap::{Parser, Subcommand};
 
+use crate::shell_init::Shell;
+
 /// Simple slip command to better organize github repositories
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
@@ -19,6 +21,35 @@ pub struct Args {
     #[clap(global = true, short, long)]
     pub personal: bool,
 
+    /// print commands, directories and registry changes instead of applying them
+    #[clap(global = true, long)]
+    pub dry_run: bool,
+
+    /// skip `post_clone`/`post_new` hooks configured for the profile
+    #[clap(global = true, long)]
+    pub no_hooks: bool,
+
+    /// log more: -v shows the commands slip runs, -vv debug details, -vvv everything
+    #[clap(global = true, short, long, parse(from_occurrences))]
+    pub verbose: u8,
+
+    /// only log errors
+    #[clap(global = true, short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// also append a full trace of every run to this file
+    #[clap(global = true, long)]
+    pub log_file: Option<String>,
+
+    /// `slip ui` prints the path picked with enter instead of running an action,
+    /// drawing itself on stderr
+    #[clap(global = true, long)]
+    pub print_path: bool,
+
+    /// like `--print-path`, but writes the path to this file (or `/dev/fd/<n>`)
+    #[clap(global = true, long, conflicts_with = "print-path")]
+    pub path_file: Option<String>,
+
     #[clap(subcommand)]
     pub command: Option<SubCommands>,
 }
@@ -35,25 +66,103 @@ pub enum SubCommands {
     Clone {
         url: String,
         dir: Option<String>,
+
+        /// repository this is a fork of, as url or `owner/repo` on the same host;
+        /// added as `upstream` remote and tracked by the default branch
+        #[clap(long)]
+        upstream: Option<String>,
     },
 
     Reconfig,
 
     List {
-        // filter repos
-        #[clap(index = 1)]
-        filter: Option<String>,
+        /// query like `cat:work tag:infra api`, see `slip ui` for the syntax
+        filter: Vec<String>,
     },
     Ui,
 
     New {
-        // repo name
+        // repo name, `owner/name` is laid out like `slip clone` would
         #[clap(index = 1)]
         repo: String,
+
+        /// add a bundled .gitignore (Go, Java, Node, Python, Rust)
+        #[clap(long)]
+        gitignore: Option<String>,
+
+        /// add a bundled LICENSE (Apache-2.0, BSD-3-Clause, MIT, Unlicense)
+        #[clap(long)]
+        license: Option<String>,
+
+        /// template of the profile to start the repository from
+        #[clap(long)]
+        template: Option<String>,
+
+        /// also create the repository on the forge of the profile and push to it
+        #[clap(long)]
+        remote: bool,
+
+        /// make the forge repository public, even if the profile creates private ones
+        #[clap(long, requires = "remote")]
+        public: bool,
     },
 
     Add {
         #[clap(index = 1)]
         repo: String,
     },
+
+    /// show branch, changes and how far forks are behind upstream
+    Status {
+        /// query selecting the repos, like for `list`
+        filter: Vec<String>,
+
+        /// fetch `upstream` of forks before comparing
+        #[clap(long)]
+        fetch: bool,
+    },
+
+    /// manage git worktrees of a registered repo
+    Worktree {
+        #[clap(subcommand)]
+        command: WorktreeCommands,
+    },
+
+    /// run an action (see `actions` in the config) on a registered repo
+    Open {
+        repo: String,
+
+        /// action to run, the first configured one (`shell` by default) if not given
+        #[clap(long)]
+        with: Option<String>,
+    },
+
+    /// tag a registered repo, prints its tags when no tags are given
+    Tag {
+        repo: String,
+        tags: Vec<String>,
+
+        /// remove the tags instead of adding them
+        #[clap(short, long)]
+        remove: bool,
+    },
+
+    /// print a `slip` wrapper that changes into the repo picked in `slip ui`,
+    /// use with `eval "$(slip shell-init bash)"`
+    ShellInit {
+        #[clap(arg_enum)]
+        shell: Shell,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum WorktreeCommands {
+    /// check out `branch` of `repo` into a new worktree
+    Add { repo: String, branch: String },
+
+    /// list worktrees, of all repos or only of `repo`
+    List { repo: Option<String> },
+
+    /// remove the worktree of `branch` from `repo`
+    Remove { repo: String, branch: String },
 }
@@ -0,0 +1,175 @@
+use std::path::Path;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::config::{Config, GitConfig};
+use crate::git::configure_git;
+use crate::repolist::Repo;
+
+/// Git operations `slip ui` runs on every marked repo, in the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkOperation {
+    Fetch,
+    Pull,
+    /// sets the git identity of the profile, like `slip reconfig`
+    Reconfig,
+}
+
+impl BulkOperation {
+    pub fn title(&self) -> &'static str {
+        match self {
+            BulkOperation::Fetch => "fetch",
+            BulkOperation::Pull => "pull --ff-only",
+            BulkOperation::Reconfig => "reconfig identity",
+        }
+    }
+
+    /// Runs on the checkout at `location` of `repo`; a one line summary of what happened or why it failed.
+    pub fn run(&self, repo: &Repo, location: &str, config: &Config) -> Result<String, String> {
+        if crate::is_dry_run() {
+            return Ok(format!("dry run, would {}", self.title()));
+        }
+        match self {
+            BulkOperation::Fetch => git_summary(location, &["fetch", "--prune"], "fetched"),
+            BulkOperation::Pull => git_summary(location, &["pull", "--ff-only"], "pulled"),
+            BulkOperation::Reconfig => {
+                let repo = Repo {
+                    location: location.to_string(),
+                    ..repo.clone()
+                };
+                configure_git(&repo, config).map_err(|err| err.to_string())?;
+                Ok(match config.get_git_config(&repo.category) {
+                    Some(GitConfig {
+                        email: Some(email), ..
+                    }) => format!("identity set to {email}"),
+                    _ => format!("no identity configured for {}", repo.category),
+                })
+            }
+        }
+    }
+}
+
+/// Last line git printed, or `default` if it printed nothing.
+fn git_summary(location: &str, args: &[&str], default: &str) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(Path::new(location))
+        .output()
+        .map_err(|err| format!("can't run git: {err}"))?;
+    let text = [&output.stdout[..], &output.stderr[..]]
+        .map(String::from_utf8_lossy)
+        .join("\n");
+    let last_line = text
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(str::to_string);
+    if output.status.success() {
+        Ok(last_line.unwrap_or_else(|| default.to_string()))
+    } else {
+        Err(last_line.unwrap_or_else(|| format!("git exited with {}", output.status)))
+    }
+}
+
+/// Outcome of a bulk operation per repo, filled in while it runs.
+pub struct BulkRun {
+    pub title: String,
+    /// label of the repo and its result, `None` while pending
+    pub results: Vec<(String, Option<Result<String, String>>)>,
+    progress: Option<Receiver<(usize, Result<String, String>)>>,
+}
+
+impl BulkRun {
+    /// Starts `operation` on `checkouts` (a repo and the location of it or one of its worktrees)
+    /// one after another on a background thread.
+    pub fn start(
+        operation: BulkOperation,
+        checkouts: Vec<(Repo, String)>,
+        config: Config,
+    ) -> BulkRun {
+        let results = checkouts
+            .iter()
+            .map(|(repo, location)| (label(repo, location), None))
+            .collect();
+        let (done, progress) = mpsc::channel();
+        thread::spawn(move || {
+            for (index, (repo, location)) in checkouts.iter().enumerate() {
+                let result = operation.run(repo, location, &config);
+                tracing::debug!(operation = operation.title(), location, ?result, "bulk");
+                if done.send((index, result)).is_err() {
+                    break;
+                }
+            }
+        });
+        BulkRun {
+            title: operation.title().to_string(),
+            results,
+            progress: Some(progress),
+        }
+    }
+
+    /// A run that already completed, for operations done right away.
+    pub fn finished(title: String, results: Vec<(String, Result<String, String>)>) -> BulkRun {
+        BulkRun {
+            title,
+            results: results
+                .into_iter()
+                .map(|(label, result)| (label, Some(result)))
+                .collect(),
+            progress: None,
+        }
+    }
+
+    /// Takes the results that arrived since the last call.
+    pub fn update(&mut self) {
+        if let Some(progress) = &self.progress {
+            while let Ok((index, result)) = progress.try_recv() {
+                self.results[index].1 = Some(result);
+            }
+        }
+    }
+
+    pub fn completed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, result)| result.is_some())
+            .count()
+    }
+}
+
+/// Name of the repo, with the worktree's directory if `location` isn't the repo itself.
+pub fn label(repo: &Repo, location: &str) -> String {
+    match repo
+        .worktrees
+        .iter()
+        .find(|worktree| worktree.location == location)
+    {
+        Some(worktree) => format!("{} ({})", repo.name, worktree.branch),
+        None => repo.name.clone(),
+    }
+}
+
+#[test]
+fn pull_reports_git_errors() {
+    let location = crate::git::init_test_repo("bulk");
+    let location = location.to_str().unwrap();
+    let repo = Repo::for_test("demo", location);
+
+    let mut bulk = BulkRun::start(
+        BulkOperation::Pull,
+        vec![(repo.clone(), location.to_string())],
+        Config::new(),
+    );
+    while bulk.completed() < 1 {
+        bulk.update();
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!("demo", bulk.results[0].0);
+    assert!(matches!(bulk.results[0].1, Some(Err(_))));
+    assert_eq!(
+        Ok("no identity configured for Work".to_string()),
+        BulkOperation::Reconfig.run(&repo, location, &Config::new())
+    );
+    std::fs::remove_dir_all(location).unwrap();
+}
@@ -0,0 +1,296 @@
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::config::{Config, UrlRewrite, WorkOrPersonal};
+use crate::git::{self, configure_git};
+use crate::hooks::{run_hooks_captured, HookKind};
+use crate::remote::{canonical_url, same_remote, RemoteUrl};
+use crate::repolist::{Repo, RepoList};
+use crate::{create_dir_all, SlipError};
+
+/// Profile for cloning `url` when none is chosen: the one whose rewrites produce
+/// urls like `url` (it was pasted with a host alias), then the default.
+pub fn profile_for_url(config: &Config, url: &str) -> WorkOrPersonal {
+    config
+        .profile_for_alias(url)
+        .or(config.default)
+        .unwrap_or(WorkOrPersonal::Personal)
+}
+
+/// Where `url` is cloned for `category`: `dir` inside the profile root, or the owner/repo
+/// path of the url laid out by the profile's `RepoNamePattern`.
+pub fn destination(
+    url: &str,
+    dir: Option<&str>,
+    category: WorkOrPersonal,
+    config: &Config,
+) -> Result<PathBuf, SlipError> {
+    let (reporoot, pattern) = match category {
+        WorkOrPersonal::Work => config.work(),
+        WorkOrPersonal::Personal => config.personal(),
+    };
+    let reporoot = Path::new(&reporoot);
+    if let Some(dir) = dir {
+        return Ok(reporoot.join(dir));
+    }
+    let path = RemoteUrl::parse(url)
+        .map(|remote| remote.path)
+        .ok_or_else(|| SlipError::InvalidUrl {
+            url: url.to_string(),
+            reason: "no owner/repo in it, pass the directory to clone into".to_string(),
+        })?;
+    Ok(reporoot.join(pattern.get_directory(path)))
+}
+
+pub enum CloneDestination {
+    /// destination doesn't exist yet or is an empty directory
+    Empty(PathBuf),
+    /// destination is already a clone of the requested url
+    SameRepo(PathBuf),
+    /// destination holds something else, a different repository if `origin` is set
+    Occupied {
+        directory: PathBuf,
+        origin: Option<String>,
+    },
+}
+
+/// What is at `directory` already, for cloning `url`.
+pub fn inspect_destination(
+    url: &str,
+    rewrites: &[UrlRewrite],
+    directory: PathBuf,
+) -> Result<CloneDestination, SlipError> {
+    if !directory.exists() || (directory.is_dir() && directory.read_dir()?.next().is_none()) {
+        return Ok(CloneDestination::Empty(directory));
+    }
+    if !git::is_repo(&directory) {
+        return Err(SlipError::DestinationExists {
+            path: directory.display().to_string(),
+            reason: "already exists and is not a git repository, refusing to clone into it"
+                .to_string(),
+        });
+    }
+    let origin = git::remote_url(&directory, "origin");
+    if let Some(origin) = &origin {
+        if same_remote(&canonical_url(origin, rewrites), url) {
+            return Ok(CloneDestination::SameRepo(directory));
+        }
+    }
+    Ok(CloneDestination::Occupied { directory, origin })
+}
+
+/// First of `<directory>-2`, `<directory>-3`, ... that doesn't exist yet.
+pub fn alternate_directory(directory: &Path) -> PathBuf {
+    let mut suffix = 2;
+    loop {
+        let mut name = directory.as_os_str().to_owned();
+        name.push(format!("-{suffix}"));
+        let candidate = PathBuf::from(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        suffix += 1;
+    }
+}
+
+/// Registry entry of the checkout at `directory`, named after it.
+pub fn registry_entry(
+    url: &str,
+    directory: PathBuf,
+    category: WorkOrPersonal,
+    upstream: Option<String>,
+) -> Result<Repo, SlipError> {
+    let name = directory
+        .file_name()
+        .ok_or_else(|| SlipError::Usage(format!("{} has no name", directory.display())))?
+        .to_string_lossy()
+        .into_owned();
+    Ok(Repo {
+        url: url.to_string(),
+        location: directory.to_string_lossy().into_owned(),
+        name,
+        category,
+        upstream,
+        tags: vec![],
+        worktrees: vec![],
+        alias: None,
+    })
+}
+
+/// Registers the checkout at `directory` and gives it the git identity of its profile.
+pub fn add_to_registry(
+    url: &str,
+    directory: PathBuf,
+    category: WorkOrPersonal,
+    upstream: Option<String>,
+    config: &Config,
+) -> Result<Repo, SlipError> {
+    let mut repos_list = RepoList::get_config()?;
+    let repo = registry_entry(url, directory, category, upstream)?;
+    configure_git(&repo, config)?;
+    repos_list.repos.push(repo.clone());
+    repos_list.save_config()?;
+    Ok(repo)
+}
+
+/// A `git clone` running in the background for `slip ui`, streaming git's progress.
+pub struct CloneJob {
+    pub url: String,
+    /// progress git printed so far, counters updating in place replace their line
+    pub lines: Vec<String>,
+    /// the registered repo once done, in a dry run the entry it would have
+    pub result: Option<Result<Repo, String>>,
+    /// `post_clone` hooks that failed after cloning
+    pub hook_failures: Vec<String>,
+    replace_last: bool,
+    events: Receiver<CloneEvent>,
+}
+
+enum CloneEvent {
+    /// a line of output and whether it ended with `\r`, to be overwritten by the next one
+    Line(String, bool),
+    HookFailed(String),
+    Done(Result<Repo, String>),
+}
+
+impl CloneJob {
+    /// Clones `clone_url` (`url` with the profile's rewrites applied) into `directory`,
+    /// registers it as `url` of `category` and runs the `post_clone` hooks if `hooks`.
+    pub fn start(
+        url: String,
+        clone_url: String,
+        directory: PathBuf,
+        category: WorkOrPersonal,
+        config: Config,
+        hooks: bool,
+    ) -> CloneJob {
+        let (events, receiver) = mpsc::channel();
+        let job_url = url.clone();
+        thread::spawn(move || {
+            let lines = events.clone();
+            let result = clone(&clone_url, &directory, move |line, replace| {
+                let _ = lines.send(CloneEvent::Line(line, replace));
+            })
+            .and_then(|()| {
+                if crate::is_dry_run() {
+                    // nothing was cloned to configure, and the registry stays as it is
+                    registry_entry(&url, directory, category, None)
+                } else {
+                    add_to_registry(&url, directory, category, None, &config)
+                }
+            })
+            .map_err(|err| err.to_string());
+            if let (Ok(repo), true) = (&result, hooks) {
+                let output = |line| {
+                    let _ = events.send(CloneEvent::Line(line, false));
+                };
+                for failure in run_hooks_captured(HookKind::PostClone, repo, &config, output) {
+                    let _ = events.send(CloneEvent::HookFailed(failure.to_string()));
+                }
+            }
+            let _ = events.send(CloneEvent::Done(result));
+        });
+        CloneJob {
+            url: job_url,
+            lines: vec![],
+            result: None,
+            hook_failures: vec![],
+            replace_last: false,
+            events: receiver,
+        }
+    }
+
+    /// Takes the progress that arrived since the last call.
+    pub fn update(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                CloneEvent::Line(line, replace) => {
+                    if self.replace_last {
+                        self.lines.pop();
+                    }
+                    self.lines.push(line);
+                    self.replace_last = replace;
+                }
+                CloneEvent::HookFailed(failure) => self.hook_failures.push(failure),
+                CloneEvent::Done(result) => self.result = Some(result),
+            }
+        }
+    }
+}
+
+/// Runs `git clone --progress`, passing each line git prints on stderr to `progress`.
+fn clone(
+    clone_url: &str,
+    directory: &Path,
+    mut progress: impl FnMut(String, bool),
+) -> Result<(), SlipError> {
+    tracing::info!(%clone_url, directory = %directory.display(), "clone");
+    if crate::is_dry_run() {
+        progress(
+            format!("would clone {clone_url} into {}", directory.display()),
+            false,
+        );
+        return Ok(());
+    }
+    if let Some(parent) = directory.parent() {
+        create_dir_all(parent)?;
+    }
+    let mut child = Command::new("git")
+        .args(["clone", "--progress", "--", clone_url])
+        .arg(directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|source| SlipError::Spawn {
+            command: format!("git clone {clone_url}"),
+            source,
+        })?;
+    if let Some(stderr) = child.stderr.take() {
+        let mut line = vec![];
+        for byte in BufReader::new(stderr).bytes() {
+            match byte? {
+                end @ (b'\r' | b'\n') => {
+                    if !line.is_empty() {
+                        progress(String::from_utf8_lossy(&line).into_owned(), end == b'\r');
+                        line.clear();
+                    }
+                }
+                byte => line.push(byte),
+            }
+        }
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(SlipError::Git {
+            command: format!("git clone {clone_url}"),
+            status,
+        });
+    }
+    Ok(())
+}
+
+#[test]
+fn clone_destinations() {
+    let config: Config = toml::from_str(
+        r#"
+        [work]
+        root = "/work"
+        pattern = "Hyphen"
+        "#,
+    )
+    .unwrap();
+    let url = "git@github.com:acme/api.git";
+    assert_eq!(
+        PathBuf::from("/work/acme-api"),
+        destination(url, None, WorkOrPersonal::Work, &config).unwrap()
+    );
+    assert_eq!(
+        PathBuf::from("/work/mine"),
+        destination(url, Some("mine"), WorkOrPersonal::Work, &config).unwrap()
+    );
+    assert!(destination("not a url", None, WorkOrPersonal::Work, &config).is_err());
+}
@@ -1,12 +1,100 @@
+use std::collections::BTreeMap;
 use std::fmt::Display;
 
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     pub default: Option<WorkOrPersonal>,
     pub work: Option<RepoRoot>,
     pub personal: Option<RepoRoot>,
+    /// what `slip ui` and `slip open` can do with a repo, `default_actions` if unset
+    pub actions: Option<Vec<Action>>,
+    pub ui: Option<UiConfig>,
+}
+
+/// `[ui]`, how `slip ui` behaves.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct UiConfig {
+    /// capture the mouse to scroll and click, `true` unless set; the terminal can't select text while it does
+    pub mouse: Option<bool>,
+    pub theme: Option<ThemeConfig>,
+    pub keys: Option<KeysConfig>,
+}
+
+/// `[ui.keys]`, what keys do in `slip ui` besides running actions.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct KeysConfig {
+    /// mode the ui starts in, `Insert` unless set
+    pub start: Option<KeyMode>,
+    /// keys like `j` or `ctrl-d` and the command they run, like `next` or `page-down`,
+    /// added to the defaults of the mode; `none` unbinds a default key
+    pub normal: Option<BTreeMap<String, String>>,
+    pub insert: Option<BTreeMap<String, String>>,
+    /// keys of the bulk menu and the command they run, like `fetch` or `remove`
+    pub bulk: Option<BTreeMap<String, String>>,
+}
+
+/// How `slip ui` reads keys: in `Insert` characters type into the search, in `Normal` they are
+/// commands like vim's.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMode {
+    Normal,
+    Insert,
+}
+
+/// `[ui.theme]`, colors and layout of `slip ui`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ThemeConfig {
+    /// `Light` unless set, or `NoColor` when the `NO_COLOR` environment variable is set
+    pub preset: Option<ThemePreset>,
+    /// percent of the width the list takes next to the preview, 55 unless set
+    pub list_width: Option<u16>,
+    /// percent of the height the filter box and the cheatsheet take, 10 and 5 unless set
+    pub filter_height: Option<u16>,
+    pub cheatsheet_height: Option<u16>,
+    /// show each repo on one line, its path after the name, instead of two
+    pub one_line: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemePreset {
+    Dark,
+    Light,
+    HighContrast,
+    /// only bold, underlined and reversed text
+    NoColor,
+}
+
+/// A command run on a repo, from `slip ui` by its key or with `slip open --with <name>`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Action {
+    pub name: String,
+    /// run through the shell inside the repo, `{path}`, `{name}` and `{url}` are replaced
+    pub command: String,
+    /// key starting it in `slip ui`, like `enter`, `insert`, `f2`, `ctrl-e` or `alt-i`
+    pub key: Option<String>,
+}
+
+/// Actions without configuration: a shell on `enter` and vscode on `insert`.
+pub fn default_actions() -> Vec<Action> {
+    let shell = if cfg!(target_os = "windows") {
+        "cmd"
+    } else {
+        "\"${SHELL:-sh}\""
+    };
+    vec![
+        Action {
+            name: "shell".to_string(),
+            command: shell.to_string(),
+            key: Some("enter".to_string()),
+        },
+        Action {
+            name: "code".to_string(),
+            command: "code {path}".to_string(),
+            key: Some("insert".to_string()),
+        },
+    ]
 }
 
 #[derive(Deserialize, Debug, Serialize, PartialEq, Eq, Clone, Copy)]
@@ -35,6 +123,60 @@ pub struct RepoRoot {
     pub pattern: Option<RepoNamePattern>,
     #[serde(rename = "git")]
     pub git_config: Option<GitConfig>,
+    /// commands run inside the repo after `slip clone`
+    pub post_clone: Option<Vec<String>>,
+    /// commands run inside the repo after `slip new`
+    pub post_new: Option<Vec<String>>,
+    /// url rewrites applied before cloning, like git's `url.<url>.insteadOf`
+    pub rewrite: Option<Vec<UrlRewrite>>,
+    /// initial branch of repos created with `slip new`, git's default if unset
+    pub default_branch: Option<String>,
+    /// templates for `slip new --template <name>`, a local directory or a git url each
+    pub templates: Option<BTreeMap<String, String>>,
+    /// forge `slip new --remote` creates the remote repository on
+    pub forge: Option<ForgeConfig>,
+    /// where `slip worktree add` puts worktrees, supports `{location}`, `{name}` and `{branch}`
+    pub worktree_layout: Option<String>,
+    /// commands replacing those of actions with the same name for repos of the profile
+    pub actions: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ForgeConfig {
+    pub kind: ForgeKind,
+    /// api root, required for `Gitea`, defaults to the public instance for `GitHub` and `GitLab`
+    pub api_url: Option<String>,
+    /// environment variable holding the api token, defaults to `GITHUB_TOKEN`, `GITLAB_TOKEN` or `GITEA_TOKEN`
+    pub token_env: Option<String>,
+    /// organization or group to create repositories under instead of the user
+    pub owner: Option<String>,
+    /// visibility of created repositories, private unless set to `false`
+    pub private: Option<bool>,
+    /// url recorded as `origin`, `Ssh` unless set
+    pub protocol: Option<CloneProtocol>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+    /// Gitea and Forgejo, which share the api
+    Gitea,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloneProtocol {
+    Ssh,
+    Https,
+}
+
+/// Urls starting with `instead_of` are cloned as if they started with `url`,
+/// for example `url = "git@github-work:"` for `instead_of = "git@github.com:"`
+/// selects the `github-work` host alias of `~/.ssh/config`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UrlRewrite {
+    pub url: String,
+    pub instead_of: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Copy)]
@@ -50,7 +192,7 @@ impl RepoNamePattern {
             RepoNamePattern::Recursive => path,
             RepoNamePattern::Hyphen => path.replace('/', "-"),
             RepoNamePattern::Plain => {
-                let at = path.rfind('/').unwrap() + 1;
+                let at = path.rfind('/').map_or(0, |at| at + 1);
                 path[at..path.len()].to_string()
             }
         }
@@ -73,15 +215,121 @@ fn teste2() {
         "asdf-asdfadf".to_string(),
         RepoNamePattern::Hyphen.get_directory("asdf/asdfadf".to_string())
     );
+
+    assert_eq!(
+        "asdfadf".to_string(),
+        RepoNamePattern::Plain.get_directory("asdfadf".to_string())
+    );
 }
 
+const DEFAULT_WORKTREE_LAYOUT: &str = "{location}.worktrees/{branch}";
+
 impl RepoRoot {
     fn get_pattern(&self) -> RepoNamePattern {
         *self.pattern.as_ref().unwrap_or(&RepoNamePattern::Recursive)
     }
 }
 
+/// Directory for the worktree of `branch` of the repo at `location`. `feature/x` nests
+/// directories like its ref does, git keeps `feature` from being a branch next to it.
+pub fn worktree_directory(
+    layout: Option<&str>,
+    location: &str,
+    name: &str,
+    branch: &str,
+) -> String {
+    let location = location.trim_end_matches(['/', '\\']);
+    let layout = shellexpand::tilde(layout.unwrap_or(DEFAULT_WORKTREE_LAYOUT));
+    layout
+        .replace("{location}", location)
+        .replace("{name}", name)
+        .replace("{branch}", branch)
+}
+
+#[test]
+fn worktree_layouts() {
+    assert_eq!(
+        "/projects/slip.worktrees/feature/ui",
+        worktree_directory(None, "/projects/slip/", "slip", "feature/ui")
+    );
+    // branches that differ only in `/` get their own worktrees
+    assert_ne!(
+        worktree_directory(None, "/projects/slip", "slip", "feature/ui"),
+        worktree_directory(None, "/projects/slip", "slip", "feature-ui")
+    );
+    assert_eq!(
+        "/worktrees/slip/main",
+        worktree_directory(
+            Some("/worktrees/{name}/{branch}"),
+            "/projects/slip",
+            "slip",
+            "main"
+        )
+    );
+}
+
 impl Config {
+    pub fn profile(&self, category: &WorkOrPersonal) -> Option<&RepoRoot> {
+        match category {
+            WorkOrPersonal::Work => self.work.as_ref(),
+            WorkOrPersonal::Personal => self.personal.as_ref(),
+        }
+    }
+    pub fn rewrites(&self, category: &WorkOrPersonal) -> &[UrlRewrite] {
+        self.profile(category)
+            .and_then(|profile| profile.rewrite.as_deref())
+            .unwrap_or_default()
+    }
+    /// Profile whose rewrites produce urls like `url`, for urls pasted with a host alias.
+    pub fn profile_for_alias(&self, url: &str) -> Option<WorkOrPersonal> {
+        [WorkOrPersonal::Work, WorkOrPersonal::Personal]
+            .into_iter()
+            .find(|category| {
+                self.rewrites(category)
+                    .iter()
+                    .any(|rewrite| url.starts_with(&rewrite.url))
+            })
+    }
+    pub fn default_branch(&self, category: &WorkOrPersonal) -> Option<&str> {
+        self.profile(category)
+            .and_then(|profile| profile.default_branch.as_deref())
+    }
+    pub fn template(&self, category: &WorkOrPersonal, name: &str) -> Option<&str> {
+        self.profile(category)
+            .and_then(|profile| profile.templates.as_ref())
+            .and_then(|templates| templates.get(name))
+            .map(String::as_str)
+    }
+    /// Configured actions, or the default ones.
+    pub fn actions(&self) -> Vec<Action> {
+        self.actions.clone().unwrap_or_else(default_actions)
+    }
+
+    /// Action `name` with the command of the profile's override, if it has one.
+    pub fn action(&self, category: &WorkOrPersonal, name: &str) -> Option<Action> {
+        let mut action = self
+            .actions()
+            .into_iter()
+            .find(|action| action.name == name)?;
+        if let Some(command) = self
+            .profile(category)
+            .and_then(|profile| profile.actions.as_ref())
+            .and_then(|actions| actions.get(name))
+        {
+            action.command = command.clone();
+        }
+        Some(action)
+    }
+
+    /// Whether `slip ui` captures the mouse.
+    pub fn mouse(&self) -> bool {
+        self.ui.as_ref().and_then(|ui| ui.mouse).unwrap_or(true)
+    }
+
+    pub fn worktree_layout(&self, category: &WorkOrPersonal) -> Option<&str> {
+        self.profile(category)
+            .and_then(|profile| profile.worktree_layout.as_deref())
+    }
     pub fn get_git_config(&self, category: &WorkOrPersonal) -> Option<GitConfig> {
         match category {
             WorkOrPersonal::Work => self
@@ -96,13 +344,13 @@ impl Config {
         .flatten()
     }
     pub fn work(&self) -> (String, RepoNamePattern) {
-        if self.work.is_some() && self.work.as_ref().unwrap().root.is_some() {
-            (
-                String::from(shellexpand::tilde(
-                    self.work.as_ref().unwrap().root.as_ref().unwrap(),
-                )),
-                self.work.as_ref().unwrap().get_pattern(),
-            )
+        if let Some(
+            work @ RepoRoot {
+                root: Some(root), ..
+            },
+        ) = &self.work
+        {
+            (String::from(shellexpand::tilde(root)), work.get_pattern())
         } else {
             (
                 String::from(shellexpand::tilde("~/projects/work")),
@@ -112,12 +360,15 @@ impl Config {
     }
 
     pub fn personal(&self) -> (String, RepoNamePattern) {
-        if self.personal.is_some() && self.personal.as_ref().unwrap().root.is_some() {
+        if let Some(
+            personal @ RepoRoot {
+                root: Some(root), ..
+            },
+        ) = &self.personal
+        {
             (
-                String::from(shellexpand::tilde(
-                    self.personal.as_ref().unwrap().root.as_ref().unwrap(),
-                )),
-                self.personal.as_ref().unwrap().get_pattern(),
+                String::from(shellexpand::tilde(root)),
+                personal.get_pattern(),
             )
         } else {
             (
@@ -126,17 +377,47 @@ impl Config {
             )
         }
     }
-    pub fn default(&self) -> (String, RepoNamePattern) {
-        match self.default.as_ref().unwrap() {
-            WorkOrPersonal::Personal => self.personal(),
-            WorkOrPersonal::Work => self.work(),
-        }
-    }
     pub fn new() -> Config {
         Config {
             work: None,
             personal: None,
             default: Some(WorkOrPersonal::Work),
+            actions: None,
+            ui: None,
         }
     }
 }
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn profile_overrides_action() {
+    let config: Config = toml::from_str(
+        r#"
+        [[actions]]
+        name = "editor"
+        command = "nvim {path}"
+        key = "ctrl-e"
+
+        [work.actions]
+        editor = "idea {path}"
+        "#,
+    )
+    .unwrap();
+    let command = |category| config.action(&category, "editor").unwrap().command;
+    assert_eq!("idea {path}", command(WorkOrPersonal::Work));
+    assert_eq!("nvim {path}", command(WorkOrPersonal::Personal));
+    assert_eq!(None, config.action(&WorkOrPersonal::Work, "code"));
+    assert_eq!(
+        Some("insert"),
+        Config::new()
+            .action(&WorkOrPersonal::Work, "code")
+            .unwrap()
+            .key
+            .as_deref()
+    );
+}
@@ -0,0 +1,121 @@
+use std::fmt::Display;
+use std::io;
+use std::process::ExitStatus;
+
+use crate::hooks::HookFailure;
+
+/// Everything that can make a slip command fail. `main` turns it into an
+/// error message and the exit code of `exit_code`.
+#[derive(Debug)]
+pub enum SlipError {
+    /// `~/.slip.toml` (or `--config`) is not valid
+    ConfigParse {
+        path: String,
+        source: toml::de::Error,
+    },
+    /// the repo registry couldn't be read or written
+    RegistryIo {
+        path: String,
+        source: io::Error,
+    },
+    /// the repo registry exists but is not valid
+    RegistryParse {
+        path: String,
+        source: toml::de::Error,
+    },
+    /// a git (or other) command ran but exited unsuccessfully
+    Git {
+        command: String,
+        status: ExitStatus,
+    },
+    /// a command couldn't be started at all, for example because git isn't installed
+    Spawn {
+        command: String,
+        source: io::Error,
+    },
+    InvalidUrl {
+        url: String,
+        reason: String,
+    },
+    /// no registered repo matches the given name or location
+    MissingRepo(String),
+    /// the directory to create or clone into is already taken
+    DestinationExists {
+        path: String,
+        reason: String,
+    },
+    /// arguments that don't fit the configuration, like an unknown template
+    Usage(String),
+    /// the forge api refused or couldn't be reached
+    Forge(String),
+    /// the command itself succeeded, but some hooks failed
+    Hooks(Vec<HookFailure>),
+    /// the user declined a confirmation
+    Aborted,
+    Io(io::Error),
+}
+
+impl SlipError {
+    /// Process exit code, distinct per kind of failure so scripts can react.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            SlipError::Io(_) => 1,
+            SlipError::Usage(_) => 2,
+            SlipError::ConfigParse { .. } => 3,
+            SlipError::RegistryIo { .. } | SlipError::RegistryParse { .. } => 4,
+            SlipError::Git { .. } => 5,
+            SlipError::Spawn { .. } => 6,
+            SlipError::InvalidUrl { .. } => 7,
+            SlipError::MissingRepo(_) => 8,
+            SlipError::DestinationExists { .. } => 9,
+            SlipError::Forge(_) => 10,
+            SlipError::Hooks(_) => 11,
+            SlipError::Aborted => 12,
+        }
+    }
+}
+
+impl Display for SlipError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SlipError::ConfigParse { path, source } => write!(f, "invalid config {path}: {source}"),
+            SlipError::RegistryIo { path, source } => {
+                write!(f, "can't access repo registry {path}: {source}")
+            }
+            SlipError::RegistryParse { path, source } => {
+                write!(f, "invalid repo registry {path}: {source}")
+            }
+            SlipError::Git { command, status } => write!(f, "`{command}` failed with {status}"),
+            SlipError::Spawn { command, source } => write!(f, "can't run `{command}`: {source}"),
+            SlipError::InvalidUrl { url, reason } => write!(f, "invalid url {url}: {reason}"),
+            SlipError::MissingRepo(repo) => write!(f, "{repo} is not registered with slip"),
+            SlipError::DestinationExists { path, reason } => write!(f, "{path} {reason}"),
+            SlipError::Usage(message) | SlipError::Forge(message) => write!(f, "{message}"),
+            SlipError::Hooks(failures) => {
+                let failures: Vec<String> = failures.iter().map(HookFailure::to_string).collect();
+                write!(f, "{}", failures.join("\n"))
+            }
+            SlipError::Aborted => write!(f, "aborted"),
+            SlipError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for SlipError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SlipError::ConfigParse { source, .. } | SlipError::RegistryParse { source, .. } => {
+                Some(source)
+            }
+            SlipError::RegistryIo { source, .. } | SlipError::Spawn { source, .. } => Some(source),
+            SlipError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SlipError {
+    fn from(err: io::Error) -> Self {
+        SlipError::Io(err)
+    }
+}
@@ -0,0 +1,314 @@
+use serde_json::{json, Value};
+
+use crate::config::{CloneProtocol, ForgeConfig, ForgeKind};
+use crate::SlipError;
+
+/// Client for the repository api of a GitHub, GitLab or Gitea/Forgejo instance.
+pub struct Forge {
+    pub kind: ForgeKind,
+    /// api root, for example `https://api.github.com` or `https://gitlab.com/api/v4`
+    pub api_url: String,
+    /// `None` if `token_env` isn't set, which only fails once a request is sent: `--dry-run`
+    /// doesn't need a token
+    pub token: Option<String>,
+    pub token_env: String,
+}
+
+/// Repository created on a forge.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RemoteRepo {
+    pub ssh_url: String,
+    pub https_url: String,
+}
+
+impl RemoteRepo {
+    pub fn url(&self, protocol: CloneProtocol) -> &str {
+        match protocol {
+            CloneProtocol::Ssh => &self.ssh_url,
+            CloneProtocol::Https => &self.https_url,
+        }
+    }
+}
+
+impl ForgeKind {
+    fn default_api_url(&self) -> Option<&'static str> {
+        match self {
+            ForgeKind::GitHub => Some("https://api.github.com"),
+            ForgeKind::GitLab => Some("https://gitlab.com/api/v4"),
+            ForgeKind::Gitea => None,
+        }
+    }
+
+    fn default_token_env(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "GITHUB_TOKEN",
+            ForgeKind::GitLab => "GITLAB_TOKEN",
+            ForgeKind::Gitea => "GITEA_TOKEN",
+        }
+    }
+}
+
+impl Forge {
+    /// Forge of a profile, with the token read from the configured environment variable if set.
+    pub fn from_config(config: &ForgeConfig) -> Result<Forge, SlipError> {
+        let api_url = config
+            .api_url
+            .as_deref()
+            .or_else(|| config.kind.default_api_url())
+            .ok_or_else(|| SlipError::Forge(format!("forge {:?} needs `api_url`", config.kind)))?;
+        let token_env = config
+            .token_env
+            .as_deref()
+            .unwrap_or_else(|| config.kind.default_token_env());
+        Ok(Forge {
+            kind: config.kind,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token: std::env::var(token_env).ok(),
+            token_env: token_env.to_string(),
+        })
+    }
+
+    /// Creates repository `name` for the authenticated user, or under `owner` (an
+    /// organization or group) when given. An `owner` that is the user's own login creates a
+    /// personal repository too.
+    pub fn create_repo(
+        &self,
+        name: &str,
+        owner: Option<&str>,
+        private: bool,
+    ) -> Result<RemoteRepo, SlipError> {
+        let response = match self.kind {
+            ForgeKind::GitHub | ForgeKind::Gitea => {
+                let path = match owner {
+                    Some(owner) if !owner.eq_ignore_ascii_case(&self.login()?) => {
+                        format!("/orgs/{owner}/repos")
+                    }
+                    _ => "/user/repos".to_string(),
+                };
+                self.post(&path, json!({ "name": name, "private": private }))?
+            }
+            ForgeKind::GitLab => {
+                let mut body = json!({
+                    "name": name,
+                    "path": name,
+                    "visibility": if private { "private" } else { "public" },
+                });
+                if let Some(owner) = owner {
+                    body["namespace_id"] = self.gitlab_namespace_id(owner)?;
+                }
+                self.post("/projects", body)?
+            }
+        };
+        let (ssh_key, https_key) = match self.kind {
+            ForgeKind::GitHub | ForgeKind::Gitea => ("ssh_url", "clone_url"),
+            ForgeKind::GitLab => ("ssh_url_to_repo", "http_url_to_repo"),
+        };
+        let field = |key: &str| {
+            response[key]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| SlipError::Forge(format!("forge response has no `{key}`")))
+        };
+        Ok(RemoteRepo {
+            ssh_url: field(ssh_key)?,
+            https_url: field(https_key)?,
+        })
+    }
+
+    /// Login of the user the token belongs to.
+    fn login(&self) -> Result<String, SlipError> {
+        let user: Value = self
+            .request("GET", "/user")?
+            .call()
+            .map_err(|err| SlipError::Forge(format!("can't look up the token's user: {err}")))?
+            .into_json()?;
+        user["login"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| SlipError::Forge("forge response has no `login`".to_string()))
+    }
+
+    fn gitlab_namespace_id(&self, owner: &str) -> Result<Value, SlipError> {
+        let path = format!("/namespaces/{}", owner.replace('/', "%2F"));
+        let namespace: Value = self
+            .request("GET", &path)?
+            .call()
+            .map_err(|err| SlipError::Forge(format!("can't look up namespace {owner}: {err}")))?
+            .into_json()?;
+        Ok(namespace["id"].clone())
+    }
+
+    fn post(&self, path: &str, body: Value) -> Result<Value, SlipError> {
+        match self.request("POST", path)?.send_json(body) {
+            Ok(response) => Ok(response.into_json()?),
+            Err(ureq::Error::Status(status, response)) => Err(SlipError::Forge(format!(
+                "forge refused to create the repository ({status}): {}",
+                response.into_string().unwrap_or_default()
+            ))),
+            Err(err) => Err(SlipError::Forge(format!("can't reach the forge: {err}"))),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> Result<ureq::Request, SlipError> {
+        let token = self.token.as_deref().ok_or_else(|| {
+            SlipError::Forge(format!(
+                "set {} to a token of the {:?} forge",
+                self.token_env, self.kind
+            ))
+        })?;
+        let request = ureq::request(method, &format!("{}{path}", self.api_url));
+        Ok(match self.kind {
+            ForgeKind::GitHub => request
+                .set("Authorization", &format!("Bearer {token}"))
+                .set("Accept", "application/vnd.github+json"),
+            ForgeKind::GitLab => request.set("PRIVATE-TOKEN", token),
+            ForgeKind::Gitea => request.set("Authorization", &format!("token {token}")),
+        })
+    }
+}
+
+#[cfg(test)]
+fn mock_forge(
+    kind: ForgeKind,
+    responses: Vec<&'static str>,
+) -> (Forge, std::thread::JoinHandle<Vec<String>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let mut requests = vec![];
+        for body in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(length) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = length.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut content = vec![0; content_length];
+            reader.read_exact(&mut content).unwrap();
+            request.push_str(&String::from_utf8(content).unwrap());
+            let response = format!(
+                "HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            requests.push(request);
+        }
+        requests
+    });
+    let forge = Forge {
+        kind,
+        api_url,
+        token: Some("secret".to_string()),
+        token_env: "GITHUB_TOKEN".to_string(),
+    };
+    (forge, server)
+}
+
+#[test]
+fn create_github_repo() {
+    let (forge, server) = mock_forge(
+        ForgeKind::GitHub,
+        vec![
+            r#"{"login": "me"}"#,
+            r#"{"ssh_url": "git@github.com:acme/api.git", "clone_url": "https://github.com/acme/api.git"}"#,
+        ],
+    );
+    let created = forge.create_repo("api", Some("acme"), true).unwrap();
+    assert_eq!(
+        "git@github.com:acme/api.git",
+        created.url(CloneProtocol::Ssh)
+    );
+    assert_eq!(
+        "https://github.com/acme/api.git",
+        created.url(CloneProtocol::Https)
+    );
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("GET /user "));
+    assert!(requests[1].starts_with("POST /orgs/acme/repos "));
+    assert!(requests[1].contains("Authorization: Bearer secret"));
+    assert!(requests[1].ends_with(r#"{"name":"api","private":true}"#));
+}
+
+#[test]
+fn create_github_repo_for_own_login() {
+    let (forge, server) = mock_forge(
+        ForgeKind::GitHub,
+        vec![
+            r#"{"login": "Me"}"#,
+            r#"{"ssh_url": "git@github.com:me/tool.git", "clone_url": "https://github.com/me/tool.git"}"#,
+        ],
+    );
+    let created = forge.create_repo("tool", Some("me"), true).unwrap();
+    assert_eq!("git@github.com:me/tool.git", created.ssh_url);
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("GET /user "));
+    assert!(requests[1].starts_with("POST /user/repos "));
+    assert!(requests[1].ends_with(r#"{"name":"tool","private":true}"#));
+}
+
+#[test]
+fn create_gitea_repo() {
+    let (forge, server) = mock_forge(
+        ForgeKind::Gitea,
+        vec![
+            r#"{"ssh_url": "git@git.local:me/api.git", "clone_url": "https://git.local/me/api.git"}"#,
+        ],
+    );
+    let created = forge.create_repo("api", None, false).unwrap();
+    assert_eq!("git@git.local:me/api.git", created.ssh_url);
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("POST /user/repos "));
+    assert!(requests[0].contains("Authorization: token secret"));
+    assert!(requests[0].ends_with(r#"{"name":"api","private":false}"#));
+}
+
+#[test]
+fn create_gitlab_repo_in_group() {
+    let (forge, server) = mock_forge(
+        ForgeKind::GitLab,
+        vec![
+            r#"{"id": 42, "full_path": "acme/backend"}"#,
+            r#"{"ssh_url_to_repo": "git@gitlab.com:acme/backend/api.git", "http_url_to_repo": "https://gitlab.com/acme/backend/api.git"}"#,
+        ],
+    );
+    let created = forge
+        .create_repo("api", Some("acme/backend"), true)
+        .unwrap();
+    assert_eq!(
+        "https://gitlab.com/acme/backend/api.git",
+        created.url(CloneProtocol::Https)
+    );
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("GET /namespaces/acme%2Fbackend "));
+    assert!(requests[1].starts_with("POST /projects "));
+    assert!(requests[1].contains("PRIVATE-TOKEN: secret"));
+    assert!(requests[1].contains(r#""namespace_id":42"#));
+    assert!(requests[1].contains(r#""visibility":"private""#));
+}
+
+#[test]
+fn token_is_only_needed_to_send() {
+    let config: ForgeConfig = toml::from_str(
+        r#"
+        kind = "GitHub"
+        token_env = "SLIP_TEST_UNSET_TOKEN"
+        "#,
+    )
+    .unwrap();
+    let forge = Forge::from_config(&config).unwrap();
+    assert_eq!("https://api.github.com", forge.api_url);
+    let err = forge.create_repo("api", None, true).unwrap_err();
+    assert!(err.to_string().contains("SLIP_TEST_UNSET_TOKEN"), "{err}");
+}
@@ -0,0 +1,137 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::{Config, GitConfig};
+use crate::repolist::Repo;
+use crate::{run, SlipError};
+
+/// Empty repository on `main` in a directory of the temp dir named after `test`, to be
+/// removed by the test.
+#[cfg(test)]
+pub(crate) fn init_test_repo(test: &str) -> std::path::PathBuf {
+    let location = std::env::temp_dir().join(format!("slip {test} test {}", std::process::id()));
+    std::fs::create_dir_all(&location).unwrap();
+    let args = ["init", "--quiet", "--initial-branch=main"];
+    run("git", &args, Some(location.to_str().unwrap())).unwrap();
+    location
+}
+
+/// Whether `dir` is the top level of a git work tree.
+pub fn is_repo(dir: &Path) -> bool {
+    dir.join(".git").exists()
+}
+
+/// Trimmed stdout of `git <args>` run in `dir`, `None` if git fails or prints nothing.
+pub fn output(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Some(stdout).filter(|stdout| !stdout.is_empty())
+}
+
+/// Url of `remote` in the repository at `dir`, if the remote is configured.
+pub fn remote_url(dir: &Path, remote: &str) -> Option<String> {
+    output(dir, &["remote", "get-url", remote])
+}
+
+/// Whether `reference` (for example `refs/heads/main`) exists in the repository at `dir`.
+pub fn has_ref(dir: &Path, reference: &str) -> bool {
+    Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", reference])
+        .current_dir(dir)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Whether the work tree at `dir` has uncommitted changes or untracked files.
+pub fn is_dirty(dir: &Path) -> bool {
+    output(dir, &["status", "--porcelain"]).is_some()
+}
+
+/// Checked out branch, `None` on a detached HEAD.
+pub fn current_branch(dir: &Path) -> Option<String> {
+    output(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"])
+}
+
+/// Default branch of `remote` as recorded in `refs/remotes/<remote>/HEAD`, for example `upstream/main`.
+pub fn remote_head(dir: &Path, remote: &str) -> Option<String> {
+    output(
+        dir,
+        &[
+            "symbolic-ref",
+            "--quiet",
+            "--short",
+            &format!("refs/remotes/{remote}/HEAD"),
+        ],
+    )
+}
+
+/// Number of commits in `from..to`.
+pub fn count_commits(dir: &Path, from: &str, to: &str) -> Option<usize> {
+    output(dir, &["rev-list", "--count", &format!("{from}..{to}")])?
+        .parse()
+        .ok()
+}
+
+/// Sets `user.email` and `user.name` of the repo to the identity of its profile.
+pub fn configure_git(repo: &Repo, config: &Config) -> Result<(), SlipError> {
+    tracing::info!(
+        location = %repo.location,
+        profile = %repo.category,
+        "configure git identity"
+    );
+    let git_config: Option<GitConfig> = config.get_git_config(&repo.category);
+    if let Some(GitConfig { email, name }) = git_config {
+        if let Some(email) = email {
+            run(
+                "git",
+                &["config", "--", "user.email", &email],
+                Some(repo.location.as_ref()),
+            )?;
+        }
+        if let Some(name) = name {
+            run(
+                "git",
+                &["config", "--", "user.name", &name],
+                Some(repo.location.as_ref()),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn configure_identity_with_spaces_and_quotes() {
+    use crate::config::RepoRoot;
+
+    let location = init_test_repo("git it's \"quoted\" $(dir)");
+
+    let name = r#"Jane "JD" O'Doe"#;
+    let email = "jane doe@example.com";
+    let mut profile: RepoRoot = toml::from_str("").unwrap();
+    profile.git_config = Some(GitConfig {
+        email: Some(email.to_string()),
+        name: Some(name.to_string()),
+    });
+    let mut config = Config::new();
+    config.work = Some(profile);
+    let repo = Repo::for_test("quoted", location.to_str().unwrap());
+    configure_git(&repo, &config).unwrap();
+
+    assert_eq!(
+        Some(name.to_string()),
+        output(&location, &["config", "user.name"])
+    );
+    assert_eq!(
+        Some(email.to_string()),
+        output(&location, &["config", "user.email"])
+    );
+    std::fs::remove_dir_all(location).unwrap();
+}
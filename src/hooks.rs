@@ -0,0 +1,157 @@
+use std::fmt::Display;
+use std::process::{Command, ExitStatus, Stdio};
+
+use crate::config::{Config, RepoRoot};
+use crate::repolist::Repo;
+use crate::{display_command, execute_with_env, is_dry_run, shell_command, SlipError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    PostClone,
+    PostNew,
+}
+
+impl Display for HookKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookKind::PostClone => write!(f, "post_clone"),
+            HookKind::PostNew => write!(f, "post_new"),
+        }
+    }
+}
+
+impl HookKind {
+    fn commands(self, profile: &RepoRoot) -> &[String] {
+        let commands = match self {
+            HookKind::PostClone => &profile.post_clone,
+            HookKind::PostNew => &profile.post_new,
+        };
+        commands.as_deref().unwrap_or_default()
+    }
+}
+
+/// A hook command that could not be run or exited unsuccessfully.
+#[derive(Debug)]
+pub struct HookFailure {
+    pub command: String,
+    pub reason: String,
+}
+
+impl Display for HookFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "hook `{}` failed: {}", self.command, self.reason)
+    }
+}
+
+/// Runs the profile's hooks of `kind` inside `repo.location`, one after another.
+///
+/// Every hook gets `SLIP_HOOK`, `SLIP_PROFILE`, `SLIP_REPO_NAME`, `SLIP_REPO_URL`
+/// and `SLIP_REPO_PATH` in its environment. A failing hook doesn't stop the
+/// remaining ones; all failures are returned to the caller for reporting.
+pub fn run_hooks(kind: HookKind, repo: &Repo, config: &Config) -> Vec<HookFailure> {
+    run_each(kind, repo, config, |shell, args, envs| {
+        execute_with_env(shell, args, Some(&repo.location), envs)
+    })
+}
+
+/// Like [`run_hooks`], passing what the hooks print to `output` line by line instead of
+/// letting them write to the terminal, for `slip ui`.
+pub fn run_hooks_captured(
+    kind: HookKind,
+    repo: &Repo,
+    config: &Config,
+    mut output: impl FnMut(String),
+) -> Vec<HookFailure> {
+    run_each(kind, repo, config, |shell, args, envs| {
+        if is_dry_run() {
+            output(format!("would run: {}", display_command(shell, args)));
+            return Ok(ExitStatus::default());
+        }
+        let captured = Command::new(shell)
+            .args(args)
+            .current_dir(&repo.location)
+            .envs(envs.iter().copied())
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|source| SlipError::Spawn {
+                command: display_command(shell, args),
+                source,
+            })?;
+        for printed in [&captured.stdout, &captured.stderr] {
+            String::from_utf8_lossy(printed)
+                .lines()
+                .for_each(|line| output(line.to_string()));
+        }
+        Ok(captured.status)
+    })
+}
+
+/// Runs each hook of `kind` with `execute`, given the shell, its arguments and the environment.
+fn run_each(
+    kind: HookKind,
+    repo: &Repo,
+    config: &Config,
+    mut execute: impl FnMut(&str, &[&str], &[(&str, &str)]) -> Result<ExitStatus, SlipError>,
+) -> Vec<HookFailure> {
+    let commands = match config.profile(&repo.category) {
+        Some(profile) => kind.commands(profile),
+        None => return vec![],
+    };
+    let hook = kind.to_string();
+    let profile = repo.category.to_string();
+    let envs = [
+        ("SLIP_HOOK", hook.as_str()),
+        ("SLIP_PROFILE", profile.as_str()),
+        ("SLIP_REPO_NAME", repo.name.as_str()),
+        ("SLIP_REPO_URL", repo.url.as_str()),
+        ("SLIP_REPO_PATH", repo.location.as_str()),
+    ];
+    commands
+        .iter()
+        .filter_map(|command| {
+            let (shell, args) = shell_command(command);
+            let reason = match execute(shell, &args, &envs) {
+                Ok(status) if status.success() => return None,
+                Ok(status) => format!("exited with {status}"),
+                Err(err) => err.to_string(),
+            };
+            Some(HookFailure {
+                command: command.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+#[test]
+fn hooks_get_the_repo_in_env_and_cwd() {
+    let location = crate::git::init_test_repo("hooks").canonicalize().unwrap();
+    let config: Config = toml::from_str(
+        r#"
+        [work]
+        post_clone = [
+            'echo "$SLIP_HOOK $SLIP_PROFILE $SLIP_REPO_NAME $SLIP_REPO_URL $SLIP_REPO_PATH $(pwd)" > env',
+            "exit 3",
+        ]
+        "#,
+    )
+    .unwrap();
+    let repo = Repo {
+        url: "https://example.com/me/demo".to_string(),
+        ..Repo::for_test("demo", location.to_str().unwrap())
+    };
+
+    let failures = run_hooks(HookKind::PostClone, &repo, &config);
+    let env = std::fs::read_to_string(location.join("env")).unwrap();
+    std::fs::remove_dir_all(&location).unwrap();
+    let location = location.display();
+    assert_eq!(
+        format!("post_clone Work demo https://example.com/me/demo {location} {location}\n"),
+        env
+    );
+    assert_eq!(1, failures.len());
+    assert_eq!("exit 3", failures[0].command);
+    assert!(failures[0].reason.contains('3'), "{}", failures[0].reason);
+    assert!(run_hooks(HookKind::PostNew, &repo, &config).is_empty());
+}
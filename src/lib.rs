@@ -1,29 +1,179 @@
-#![feature(is_sorted)]
+pub mod action;
 pub mod args;
+pub mod bulk;
+pub mod clone;
 pub mod config;
+pub mod error;
+pub mod forge;
+pub mod git;
+pub mod hooks;
+pub mod logging;
+pub mod preview;
+pub mod query;
+pub mod remote;
 pub mod repolist;
+pub mod shell_init;
+pub mod status;
+pub mod template;
+pub mod theme;
 
 pub use config::*;
+pub use error::SlipError;
 pub mod tui;
 
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::process::{Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-pub fn execute(
-    command_to_launch: String,
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// In dry-run mode commands, directory and file creation and registry writes
+/// are only printed.
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Runs `program` with `args` as separate arguments, no shell involved, so
+/// spaces and quotes in names, urls and paths reach the program unchanged.
+pub fn execute<S: AsRef<OsStr>>(
+    program: &str,
+    args: &[S],
     current_dir: Option<&str>,
-) -> Result<ExitStatus, std::io::Error> {
-    println!("{}", command_to_launch);
-    let mut execute_command;
-    if cfg!(target_os = "windows") {
-        execute_command = Command::new("cmd");
-        execute_command.args(["/C", &command_to_launch]);
-    } else {
-        execute_command = Command::new("sh");
-        execute_command.args(["-c", &command_to_launch]);
-    };
+) -> Result<ExitStatus, SlipError> {
+    execute_with_env(program, args, current_dir, &[])
+}
+
+/// Like `execute`, but a command exiting unsuccessfully is an error too.
+pub fn run<S: AsRef<OsStr>>(
+    program: &str,
+    args: &[S],
+    current_dir: Option<&str>,
+) -> Result<(), SlipError> {
+    let status = execute(program, args, current_dir)?;
+    if !status.success() {
+        return Err(SlipError::Git {
+            command: display_command(program, args),
+            status,
+        });
+    }
+    Ok(())
+}
+
+pub fn execute_with_env<S: AsRef<OsStr>>(
+    program: &str,
+    args: &[S],
+    current_dir: Option<&str>,
+    envs: &[(&str, &str)],
+) -> Result<ExitStatus, SlipError> {
+    if is_dry_run() {
+        match current_dir {
+            Some(dir) => println!("would run: {} (in {dir})", display_command(program, args)),
+            None => println!("would run: {}", display_command(program, args)),
+        }
+        return Ok(success());
+    }
+    tracing::info!(command = %display_command(program, args), dir = current_dir, "run");
+    let mut execute_command = Command::new(program);
+    execute_command.args(args);
     if let Some(dir) = current_dir {
         execute_command.current_dir(dir);
     }
-    let spawn = execute_command.spawn()?.wait()?;
+    execute_command.envs(envs.iter().copied());
+    let spawn = execute_command
+        .spawn()
+        .and_then(|mut child| child.wait())
+        .map_err(|source| SlipError::Spawn {
+            command: display_command(program, args),
+            source,
+        })?;
+    tracing::debug!(%spawn, "finished");
     Ok(spawn)
 }
+
+#[cfg(unix)]
+fn success() -> ExitStatus {
+    std::os::unix::process::ExitStatusExt::from_raw(0)
+}
+
+#[cfg(windows)]
+fn success() -> ExitStatus {
+    std::os::windows::process::ExitStatusExt::from_raw(0)
+}
+
+/// `fs::create_dir_all`, only printed in dry-run mode.
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    if is_dry_run() {
+        println!("would create directory {}", path.display());
+        return Ok(());
+    }
+    fs::create_dir_all(path)
+}
+
+/// `fs::write`, only printed in dry-run mode.
+pub fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if is_dry_run() {
+        println!("would write {} ({} bytes)", path.display(), contents.len());
+        return Ok(());
+    }
+    fs::write(path, contents)
+}
+
+/// Program and arguments running `command_line` through the platform shell,
+/// for user configured commands such as hooks.
+pub fn shell_command(command_line: &str) -> (&'static str, [&str; 2]) {
+    if cfg!(target_os = "windows") {
+        ("cmd", ["/C", command_line])
+    } else {
+        ("sh", ["-c", command_line])
+    }
+}
+
+/// `program` and `args` as they would be typed in a POSIX shell.
+pub fn display_command<S: AsRef<OsStr>>(program: &str, args: &[S]) -> String {
+    std::iter::once(quote(program))
+        .chain(
+            args.iter()
+                .map(|arg| quote(&arg.as_ref().to_string_lossy()).into_owned().into()),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quotes `arg` for a POSIX shell if it contains anything but safe characters.
+pub fn quote(arg: &str) -> Cow<'_, str> {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:@=,+%^~".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+    }
+}
+
+#[test]
+fn quote_arguments() {
+    assert_eq!("git", quote("git"));
+    assert_eq!("user.name", quote("user.name"));
+    assert_eq!(
+        "git@github.com:acme/api.git",
+        quote("git@github.com:acme/api.git")
+    );
+    assert_eq!("'Jane Doe'", quote("Jane Doe"));
+    assert_eq!(r"'O'\''Brien'", quote("O'Brien"));
+    assert_eq!("'$(rm -rf ~)'", quote("$(rm -rf ~)"));
+    assert_eq!("''", quote(""));
+    assert_eq!(
+        r#"git config user.name 'Jane "JD" Doe'"#,
+        display_command("git", &["config", "user.name", r#"Jane "JD" Doe"#])
+    );
+}
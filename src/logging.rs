@@ -0,0 +1,82 @@
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, Layer};
+
+use crate::SlipError;
+
+static STDERR_MUTED: AtomicBool = AtomicBool::new(false);
+
+/// While muted, events only reach the log file: `slip ui` mutes stderr as long as it owns the
+/// terminal, as printed events would land on top of it.
+pub fn mute_stderr(muted: bool) {
+    STDERR_MUTED.store(muted, Ordering::Relaxed);
+}
+
+fn stderr() -> Box<dyn Write> {
+    if STDERR_MUTED.load(Ordering::Relaxed) {
+        Box::new(io::sink())
+    } else {
+        Box::new(io::stderr())
+    }
+}
+
+/// Level of the events printed to stderr: warnings by default, `-q` only
+/// errors, `-v` adds the commands slip runs, `-vv` debug and `-vvv` everything.
+pub fn level(verbose: u8, quiet: bool) -> LevelFilter {
+    if quiet {
+        return LevelFilter::ERROR;
+    }
+    match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Sends events to stderr at `level` and, with `log_file`, every event
+/// including traces to the end of that file.
+pub fn init(level: LevelFilter, log_file: Option<&str>) -> Result<(), SlipError> {
+    let stderr = fmt::layer()
+        .without_time()
+        .with_target(false)
+        .with_ansi(io::stderr().is_terminal())
+        .with_writer(stderr)
+        .with_filter(level);
+    let file = match log_file {
+        Some(path) => {
+            let path = shellexpand::tilde(path);
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path.as_ref())?;
+            Some(
+                fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file))
+                    .with_filter(LevelFilter::TRACE),
+            )
+        }
+        None => None,
+    };
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .init();
+    Ok(())
+}
+
+#[test]
+fn verbosity_levels() {
+    assert_eq!(LevelFilter::WARN, level(0, false));
+    assert_eq!(LevelFilter::INFO, level(1, false));
+    assert_eq!(LevelFilter::DEBUG, level(2, false));
+    assert_eq!(LevelFilter::TRACE, level(5, false));
+    assert_eq!(LevelFilter::ERROR, level(0, true));
+}
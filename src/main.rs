@@ -1,22 +1,48 @@
-#![feature(is_sorted)]
-use std::error::Error;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 use clap::StructOpt;
-use slip_git::args::{Args, SubCommands};
-use slip_git::config::{Config, WorkOrPersonal};
-use slip_git::execute;
-use slip_git::GitConfig;
-use url::Url;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use slip_git::action::run_action;
+use slip_git::args::{Args, SubCommands, WorktreeCommands};
+use slip_git::clone::{
+    add_to_registry, alternate_directory, destination, inspect_destination, profile_for_url,
+    CloneDestination,
+};
+use slip_git::config::{worktree_directory, CloneProtocol, Config, UrlRewrite, WorkOrPersonal};
+use slip_git::forge::Forge;
+use slip_git::git::configure_git;
+use slip_git::hooks::{run_hooks, HookFailure, HookKind};
+use slip_git::query::Query;
+use slip_git::remote::{canonical_url, expand_shorthand, rewrite_url, same_remote};
+use slip_git::repolist::*;
+use slip_git::status::RepoStatus;
+use slip_git::tui;
+use slip_git::{
+    create_dir_all, git, is_dry_run, logging, run, set_dry_run, template, write_file, SlipError,
+};
 
-mod repolist;
-mod tui;
-
-use repolist::*;
-
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> ExitCode {
     let cli = Args::parse();
+    set_dry_run(cli.dry_run);
+    let level = logging::level(cli.verbose, cli.quiet);
+    if let Err(err) = logging::init(level, cli.log_file.as_deref()) {
+        eprintln!("slip: can't open log file: {err}");
+        return ExitCode::from(err.exit_code());
+    }
+    match run_command(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            tracing::debug!(error = ?err, code = err.exit_code(), "command failed");
+            eprintln!("slip: {err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+fn run_command(cli: &Args) -> Result<(), SlipError> {
     let command = match cli.command {
         Some(ref command) => command,
         None => &SubCommands::Ui,
@@ -30,194 +56,634 @@ fn main() -> Result<(), Box<dyn Error>> {
             } else {
                 None
             };
-            let launch = tui::main(category_filter)?;
-            if let Some(launch) = launch {
-                match launch.launch_type {
-                    tui::LaunchType::LaunchShell => {
-                        let shell = if cfg!(target_os = "windows") {
-                            "cmd".to_string()
-                        } else {
-                            std::env::var("SHELL").expect("shell vairable not set")
-                        };
-                        execute(shell, Some(&launch.directory))?;
-                    }
-                    tui::LaunchType::LaunchCode => {
-                        execute(format!("code {}", launch.directory), None)?;
+            let config = get_config(cli)?;
+            let pick_path = cli.print_path || cli.path_file.is_some();
+            let launches = tui::main(
+                category_filter,
+                &config,
+                pick_path,
+                cli.print_path,
+                !cli.no_hooks,
+            )?;
+            for launch in launches {
+                match launch.action {
+                    Some(action) => {
+                        // the profile of the picked repo may override the action's command
+                        let action = config
+                            .action(&launch.repo.category, &action.name)
+                            .unwrap_or(action);
+                        run_action(&action, &launch.repo, &launch.directory)?;
                     }
-                };
+                    None => match &cli.path_file {
+                        Some(path_file) => fs::write(path_file, &launch.directory)?,
+                        None => println!("{}", launch.directory),
+                    },
+                }
             }
         }
-        SubCommands::List { filter } => {
-            let filter = match filter {
-                Some(filter) => filter,
-                None => "",
+        SubCommands::Open { repo, with } => {
+            let config = get_config(cli)?;
+            let repos_list = RepoList::get_config()?;
+            let repo = repos_list
+                .find(repo)
+                .ok_or_else(|| SlipError::MissingRepo(repo.clone()))?;
+            let action = match with {
+                Some(name) => config.action(&repo.category, name).ok_or_else(|| {
+                    let names: Vec<String> = config
+                        .actions()
+                        .into_iter()
+                        .map(|action| action.name)
+                        .collect();
+                    SlipError::Usage(format!(
+                        "no action {name}, configured are: {}",
+                        names.join(", ")
+                    ))
+                })?,
+                None => {
+                    let first = config
+                        .actions()
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| SlipError::Usage("no actions configured".to_string()))?;
+                    config.action(&repo.category, &first.name).unwrap_or(first)
+                }
             };
+            run_action(&action, repo, &repo.location)?;
+        }
+        SubCommands::List { filter } => {
+            let query = parse_query(filter)?;
+            let matcher = SkimMatcherV2::default();
             let repos_list = RepoList::get_config()?;
             repos_list
                 .repos
                 .iter()
-                .filter(|repo| {
-                    repo.name.contains(&filter)
-                        || repo.location.contains(&filter)
-                        || repo.url.contains(&filter)
-                })
+                .filter(|repo| query.matches(repo, &matcher))
                 .for_each(|repo| println!("{}", repo))
         }
-        SubCommands::Clone { url, dir } => {
-            let config = get_config(&cli);
-            let category = get_profile(&cli, &config);
-            let (reporoot, pattern) = match category {
-                WorkOrPersonal::Work => config.work(),
-                WorkOrPersonal::Personal => config.personal(),
+        SubCommands::Clone { url, dir, upstream } => {
+            let config = get_config(cli)?;
+            // `-w`/`-p` win over the profile the url belongs to
+            let category = if cli.personal || cli.work {
+                get_profile(cli, &config)
+            } else {
+                profile_for_url(&config, url)
             };
-            let reporoot = Path::new(&reporoot);
-            if !reporoot.exists() {
-                fs::create_dir_all(reporoot).expect("not able to create directory")
+            let rewrites = config.rewrites(&category);
+            // registry and directory layout always use the real host, git gets the rewritten url
+            let url = &canonical_url(url, rewrites);
+            let upstream =
+                match upstream {
+                    Some(upstream) => Some(expand_shorthand(url, upstream).ok_or_else(|| {
+                        SlipError::InvalidUrl {
+                            url: upstream.clone(),
+                            reason: format!("neither a url nor owner/repo on the host of {url}"),
+                        }
+                    })?),
+                    None => None,
+                };
+            if let Some(existing) = RepoList::get_config()?
+                .repos
+                .iter()
+                .find(|repo| same_remote(&repo.url, url))
+            {
+                println!("{url} is already cloned at {}", existing.location);
+                return Ok(());
             }
-            let directory_to_clone = if let Some(dir) = dir {
-                Path::new(reporoot).join(dir)
-            } else {
-                /*
-                 *
-                 * Its created for my case. may not work for all
-                 * where git clone github.com/adsfdsafa
-                 * here, username is username of user running
-                 */
-                // https://github.com/gitignore/gitignore
-                // use pattern to better create repos
-                let strip_hostname = if url.starts_with("https://") {
-                    let from = String::from(Url::parse(url)?.path());
-                    String::from(from.split_at(1).1)
-                } else if url.starts_with("git@") {
-                    // git@github.com:gitignore/gitignore
-                    url[(url.rfind(':').unwrap() + 1)..(url.len())].to_string()
-                } else {
-                    "".to_owned()
+            let directory_to_clone = destination(url, dir.as_deref(), category, &config)?;
+            let directory_to_clone =
+                match check_clone_destination(url, rewrites, directory_to_clone)? {
+                    // an occupied destination was already swapped for an empty one or refused
+                    CloneDestination::Empty(directory)
+                    | CloneDestination::Occupied { directory, .. } => directory,
+                    CloneDestination::SameRepo(directory) => {
+                        if let Some(upstream) = &upstream {
+                            if git::remote_url(&directory, "upstream").is_none() {
+                                configure_upstream(&directory, &rewrite_url(upstream, rewrites))?;
+                            }
+                        }
+                        add_to_registry(url, directory, category, upstream, &config)?;
+                        return Ok(());
+                    }
                 };
-                Path::new(reporoot).join(pattern.get_directory(strip_hostname))
+            // only once the url is known to be clonable, so a dry run shows a plan that works
+            let (reporoot, _) = match category {
+                WorkOrPersonal::Work => config.work(),
+                WorkOrPersonal::Personal => config.personal(),
             };
-            let clone_command_str = format!(
-                "git clone {} {}",
-                url,
-                directory_to_clone
-                    .to_str()
-                    .expect("this should not error out")
+            create_dir_all(Path::new(&reporoot))?;
+            let clone_url = rewrite_url(url, rewrites);
+            tracing::info!(
+                %url,
+                %clone_url,
+                directory = %directory_to_clone.display(),
+                profile = %category,
+                "clone"
             );
-            match execute(clone_command_str, None) {
-                Ok(exit_code) => {
-                    if exit_code.success() {
-                        add_to_slip_repo_list(url, directory_to_clone, category, config)?;
-                    }
-                }
-                Err(_) => println!("child command ran into error"),
-            };
+            // `--` keeps a url starting with `-` from being read as an option
+            let clone_args = [
+                "clone".as_ref(),
+                "--".as_ref(),
+                clone_url.as_ref(),
+                directory_to_clone.as_os_str(),
+            ];
+            run("git", &clone_args, None)?;
+            if let Some(upstream) = &upstream {
+                configure_upstream(&directory_to_clone, &rewrite_url(upstream, rewrites))?;
+            }
+            let repo = add_to_registry(url, directory_to_clone, category, upstream, &config)?;
+            if !cli.no_hooks {
+                check_hooks(run_hooks(HookKind::PostClone, &repo, &config))?;
+            }
         }
         SubCommands::Reconfig => {
-            let config = cli.config();
-            let config: Config = fs::read_to_string(config)
-                .map(|x| toml::from_str(&x))
-                .unwrap_or_else(|_| Ok(Config::new()))
-                .unwrap();
+            let config = get_config(cli)?;
             let repos_list = RepoList::get_config()?;
-            println!("config is {config:?}");
             for repo in &repos_list.repos {
                 configure_git(repo, &config)?;
             }
         }
-        SubCommands::New { repo } => {
-            let config = get_config(&cli);
-            let category = get_profile(&cli, &config);
-            let (reporoot, _) = match category {
+        SubCommands::New {
+            repo,
+            gitignore,
+            license,
+            template,
+            remote,
+            public,
+        } => {
+            let config = get_config(cli)?;
+            let category = get_profile(cli, &config);
+            let (reporoot, pattern) = match category {
                 WorkOrPersonal::Work => config.work(),
                 WorkOrPersonal::Personal => config.personal(),
             };
-            let reporoot = Path::new(&reporoot).join(&repo);
-            if !reporoot.exists() {
-                fs::create_dir_all(&reporoot).expect("not able to create directory")
-            }
-            let init_command_str = format!("git init");
-            let location = reporoot.to_str().unwrap().to_owned();
-            execute(init_command_str, Some(&location))?;
+            let template_source = template
+                .as_deref()
+                .map(|template| {
+                    config.template(&category, template).ok_or_else(|| {
+                        SlipError::Usage(format!(
+                            "no template {template} in the {category} profile"
+                        ))
+                    })
+                })
+                .transpose()?;
+            let gitignore = gitignore
+                .as_deref()
+                .map(|name| {
+                    template::bundled(template::GITIGNORES, name).ok_or_else(|| {
+                        SlipError::Usage(format!("no bundled .gitignore for {name}"))
+                    })
+                })
+                .transpose()?;
+            let license = license
+                .as_deref()
+                .map(|name| {
+                    template::bundled(template::LICENSES, name)
+                        .ok_or_else(|| SlipError::Usage(format!("no bundled license {name}")))
+                })
+                .transpose()?;
+            let repo_path = repo.trim_matches('/');
+            let (owner, repo_name) = match repo_path.rsplit_once('/') {
+                Some((owner, name)) => (Some(owner), name),
+                None => (None, repo_path),
+            };
+            // same directory `slip clone` would use for owner/name
+            let reporoot = Path::new(&reporoot).join(pattern.get_directory(repo_path.to_string()));
+            create_dir_all(&reporoot)?;
+            let initial_branch = config
+                .default_branch(&category)
+                .map(|branch| format!("--initial-branch={branch}"));
+            let init_args: Vec<&str> = std::iter::once("init")
+                .chain(initial_branch.as_deref())
+                .collect();
+            let location = reporoot.to_string_lossy().into_owned();
+            run("git", &init_args, Some(&location))?;
             let mut repos_list = RepoList::get_config()?;
-            let repo = Repo {
+            let mut repo = Repo {
                 url: "".to_string(),
                 location,
-                name: repo.to_owned(),
+                name: reporoot
+                    .file_name()
+                    .map_or(repo_name.into(), |name| name.to_string_lossy().into_owned()),
                 category,
+                upstream: None,
+                tags: vec![],
+                worktrees: vec![],
+                alias: None,
             };
             configure_git(&repo, &config)?;
-            repos_list.repos.push(repo);
-            repos_list.save_config();
+            let variables = TemplateVariables::new(repo_name, &category, &config);
+            if let Some(source) = template_source {
+                apply_template(source, &repo.location, &variables.as_slice())?;
+            }
+            if let Some(gitignore) = gitignore {
+                let path = reporoot.join(".gitignore");
+                // a template may bring its own .gitignore, the bundled one is appended
+                let existing = fs::read_to_string(&path).unwrap_or_default();
+                let separator = if existing.is_empty() || existing.ends_with('\n') {
+                    ""
+                } else {
+                    "\n"
+                };
+                write_file(&path, &format!("{existing}{separator}{gitignore}"))?;
+            }
+            if let Some(license) = license {
+                write_file(
+                    &reporoot.join("LICENSE"),
+                    &template::substitute(license, &variables.as_slice()),
+                )?;
+            }
+            if template.is_some() || gitignore.is_some() || license.is_some() {
+                let message = match template {
+                    Some(template) => format!("Initial commit from template {template}"),
+                    None => "Initial commit".to_string(),
+                };
+                run_git(&["add", "-A"], &repo.location)?;
+                run_git(&["commit", "-m", &message], &repo.location)?;
+            }
+            // the local repo is registered even if creating or pushing to the remote one fails
+            let published = if *remote {
+                create_remote_repo(&repo, repo_name, owner, *public, &config).and_then(|url| {
+                    repo.url = url;
+                    push_to_new_remote(&repo, &config)
+                })
+            } else {
+                Ok(())
+            };
+            repos_list.repos.push(repo.clone());
+            repos_list.save_config()?;
+            published?;
+            if !cli.no_hooks {
+                check_hooks(run_hooks(HookKind::PostNew, &repo, &config))?;
+            }
         }
         SubCommands::Add { repo } => {
-            let config = get_config(&cli);
-            let category = get_profile(&cli, &config);
-            add_to_slip_repo_list("", repo.into(), category, config)?;
+            let config = get_config(cli)?;
+            let category = get_profile(cli, &config);
+            add_to_registry("", repo.into(), category, None, &config)?;
+        }
+        SubCommands::Status { filter, fetch } => {
+            let query = parse_query(filter)?;
+            let matcher = SkimMatcherV2::default();
+            let repos_list = RepoList::get_config()?;
+            for repo in repos_list
+                .repos
+                .iter()
+                .filter(|repo| query.matches(repo, &matcher))
+            {
+                if *fetch && repo.upstream.is_some() {
+                    run("git", &["fetch", "upstream"], Some(&repo.location))?;
+                }
+                println!("{}: {}", repo.name, RepoStatus::collect(repo));
+            }
+        }
+        SubCommands::Worktree { command } => {
+            let config = get_config(cli)?;
+            manage_worktrees(command, &config)?;
         }
+        SubCommands::Tag { repo, tags, remove } => {
+            let mut repos_list = RepoList::get_config()?;
+            let repo = repos_list
+                .find_mut(repo)
+                .ok_or_else(|| SlipError::MissingRepo(repo.clone()))?;
+            if tags.is_empty() {
+                println!("{}", repo.tags.join(" "));
+                return Ok(());
+            }
+            if *remove {
+                repo.tags.retain(|tag| !tags.contains(tag));
+            } else {
+                repo.add_tags(tags);
+            }
+            repos_list.save_config()?;
+        }
+        SubCommands::ShellInit { shell } => print!("{}", shell.script()),
     };
     Ok(())
 }
 
-fn add_to_slip_repo_list(
-    url: &str,
-    directory_to_clone: std::path::PathBuf,
-    category: WorkOrPersonal,
-    config: Config,
-) -> Result<(), Box<dyn Error>> {
+/// Values substituted for `{{name}}`, `{{profile}}`, `{{author_name}}`,
+/// `{{author_email}}` and `{{year}}` in templates and licenses.
+struct TemplateVariables {
+    name: String,
+    profile: String,
+    author_name: String,
+    author_email: String,
+    year: String,
+}
+
+impl TemplateVariables {
+    fn new(name: &str, category: &WorkOrPersonal, config: &Config) -> TemplateVariables {
+        let git_config = config.get_git_config(category);
+        TemplateVariables {
+            name: name.to_string(),
+            profile: category.to_string().to_lowercase(),
+            author_name: git_config
+                .as_ref()
+                .and_then(|git| git.name.clone())
+                .unwrap_or_default(),
+            author_email: git_config.and_then(|git| git.email).unwrap_or_default(),
+            year: template::current_year().to_string(),
+        }
+    }
+
+    fn as_slice(&self) -> [(&str, &str); 5] {
+        [
+            ("name", &self.name),
+            ("profile", &self.profile),
+            ("author_name", &self.author_name),
+            ("author_email", &self.author_email),
+            ("year", &self.year),
+        ]
+    }
+}
+
+/// Copies the template at `source`, a local directory or a git url, into `location`.
+fn apply_template(
+    source: &str,
+    location: &str,
+    variables: &[(&str, &str)],
+) -> Result<(), SlipError> {
+    if is_dry_run() {
+        println!("would copy template {source} into {location}");
+        return Ok(());
+    }
+    let local_source = shellexpand::tilde(source);
+    if Path::new(local_source.as_ref()).is_dir() {
+        template::render(
+            Path::new(local_source.as_ref()),
+            Path::new(location),
+            variables,
+        )?;
+        return Ok(());
+    }
+    // anything that isn't a local directory is cloned
+    let checkout = std::env::temp_dir().join(format!("slip-template-{}", std::process::id()));
+    let clone_args = [
+        "clone".as_ref(),
+        "--depth".as_ref(),
+        "1".as_ref(),
+        "--".as_ref(),
+        source.as_ref(),
+        checkout.as_os_str(),
+    ];
+    let cloned = run("git", &clone_args, Some(location))
+        .and_then(|_| Ok(template::render(&checkout, Path::new(location), variables)?));
+    if checkout.exists() {
+        fs::remove_dir_all(&checkout)?;
+    }
+    cloned
+}
+
+/// Creates `repo` on the forge of its profile and returns the url of the created repository.
+/// `owner` from `slip new owner/name` takes precedence over the owner configured for the forge;
+/// when it is the token's own user, the repository is created as a personal one.
+fn create_remote_repo(
+    repo: &Repo,
+    name: &str,
+    owner: Option<&str>,
+    public: bool,
+    config: &Config,
+) -> Result<String, SlipError> {
+    let forge_config = config
+        .profile(&repo.category)
+        .and_then(|profile| profile.forge.as_ref())
+        .ok_or_else(|| {
+            SlipError::Usage(format!(
+                "no forge configured for the {} profile",
+                repo.category
+            ))
+        })?;
+    let forge = Forge::from_config(forge_config)?;
+    let private = !public && forge_config.private.unwrap_or(true);
+    let owner = owner.or(forge_config.owner.as_deref());
+    if is_dry_run() {
+        println!(
+            "would create {} repository {}{name} on {}",
+            if private { "private" } else { "public" },
+            owner.map(|owner| format!("{owner}/")).unwrap_or_default(),
+            forge.api_url
+        );
+        return Ok(format!("<{name} on {}>", forge.api_url));
+    }
+    let created = forge.create_repo(name, owner, private)?;
+    Ok(created
+        .url(forge_config.protocol.unwrap_or(CloneProtocol::Ssh))
+        .to_string())
+}
+
+/// Adds `repo.url` as `origin` and pushes to it, with an empty initial commit if there is none yet.
+fn push_to_new_remote(repo: &Repo, config: &Config) -> Result<(), SlipError> {
+    let rewrites = config.rewrites(&repo.category);
+    run_git(
+        &[
+            "remote",
+            "add",
+            "--",
+            "origin",
+            &rewrite_url(&repo.url, rewrites),
+        ],
+        &repo.location,
+    )?;
+    if !git::has_ref(Path::new(&repo.location), "HEAD") {
+        run_git(
+            &["commit", "--allow-empty", "-m", "Initial commit"],
+            &repo.location,
+        )?;
+    }
+    run_git(&["push", "-u", "origin", "HEAD"], &repo.location)
+}
+
+/// Adds `upstream` as remote of the fork at `directory`, fetches it and makes the
+/// checked out branch track the default branch of `upstream`.
+fn configure_upstream(directory: &Path, upstream: &str) -> Result<(), SlipError> {
+    let dir = &directory.to_string_lossy();
+    run_git(&["remote", "add", "--", "upstream", upstream], dir)?;
+    run_git(&["fetch", "upstream"], dir)?;
+    run_git(&["remote", "set-head", "upstream", "--auto"], dir)?;
+    if let (Some(branch), Some(upstream_head)) = (
+        git::current_branch(directory),
+        git::remote_head(directory, "upstream"),
+    ) {
+        run_git(
+            &[
+                "branch",
+                &format!("--set-upstream-to={upstream_head}"),
+                &branch,
+            ],
+            dir,
+        )?;
+    }
+    Ok(())
+}
+
+/// Runs git with `args` in `dir`, a command exiting unsuccessfully is an error.
+fn run_git(args: &[&str], dir: &str) -> Result<(), SlipError> {
+    run("git", args, Some(dir))
+}
+
+fn manage_worktrees(command: &WorktreeCommands, config: &Config) -> Result<(), SlipError> {
     let mut repos_list = RepoList::get_config()?;
-    let repo = Repo {
-        url: url.to_string(),
-        location: directory_to_clone.to_string_lossy().into_owned(),
-        name: directory_to_clone
-            .file_name()
-            .unwrap()
-            .to_string_lossy()
-            .into_owned(),
-        category,
-    };
-    configure_git(&repo, &config)?;
-    repos_list.repos.push(repo);
-    repos_list.save_config();
+    match command {
+        WorktreeCommands::Add { repo, branch } => {
+            // git would take it for an option
+            if branch.starts_with('-') {
+                return Err(SlipError::Usage(format!(
+                    "`{branch}` is not a branch name, those don't start with -"
+                )));
+            }
+            let repo = repos_list
+                .find_mut(repo)
+                .ok_or_else(|| SlipError::MissingRepo(repo.clone()))?;
+            if repo
+                .worktrees
+                .iter()
+                .any(|worktree| &worktree.branch == branch)
+            {
+                return Err(SlipError::Usage(format!(
+                    "{} already has a worktree for {branch}",
+                    repo.name
+                )));
+            }
+            let location = worktree_directory(
+                config.worktree_layout(&repo.category),
+                &repo.location,
+                &repo.name,
+                branch,
+            );
+            let repo_dir = Path::new(&repo.location);
+            // existing local or remote branches are checked out, anything else is created
+            let add_args = if git::has_ref(repo_dir, &format!("refs/heads/{branch}"))
+                || git::has_ref(repo_dir, &format!("refs/remotes/origin/{branch}"))
+            {
+                vec!["worktree", "add", "--", &location, branch]
+            } else {
+                vec!["worktree", "add", "-b", branch, "--", &location]
+            };
+            run_git(&add_args, &repo.location)?;
+            repo.worktrees.push(Worktree {
+                branch: branch.to_owned(),
+                location,
+            });
+            repos_list.save_config()?;
+        }
+        WorktreeCommands::List { repo } => {
+            let repos = match repo {
+                Some(repo) => vec![repos_list
+                    .find(repo)
+                    .ok_or_else(|| SlipError::MissingRepo(repo.clone()))?],
+                None => repos_list.repos.iter().collect(),
+            };
+            for repo in repos.into_iter().filter(|repo| !repo.worktrees.is_empty()) {
+                println!("{} ({})", repo.name, repo.location);
+                for worktree in &repo.worktrees {
+                    println!("    {}: {}", worktree.branch, worktree.location);
+                }
+            }
+        }
+        WorktreeCommands::Remove { repo, branch } => {
+            let repo = repos_list
+                .find_mut(repo)
+                .ok_or_else(|| SlipError::MissingRepo(repo.clone()))?;
+            let position = repo
+                .worktrees
+                .iter()
+                .position(|worktree| &worktree.branch == branch)
+                .ok_or_else(|| {
+                    SlipError::Usage(format!("{} has no worktree for {branch}", repo.name))
+                })?;
+            let remove_args = ["worktree", "remove", &repo.worktrees[position].location];
+            run_git(&remove_args, &repo.location)?;
+            repo.worktrees.remove(position);
+            repos_list.save_config()?;
+        }
+    }
     Ok(())
 }
 
-fn get_config(cli: &Args) -> Config {
-    let config = cli.config();
-    let config: Config = fs::read_to_string(config)
-        .map(|x| toml::from_str(&x))
-        .unwrap_or_else(|_| Ok(Config::new()))
-        .unwrap();
-    config
+/// Destination for cloning `url` into `directory`, asking to use another one if it holds a
+/// different repository.
+fn check_clone_destination(
+    url: &str,
+    rewrites: &[UrlRewrite],
+    directory: PathBuf,
+) -> Result<CloneDestination, SlipError> {
+    match inspect_destination(url, rewrites, directory)? {
+        CloneDestination::SameRepo(directory) => {
+            println!(
+                "{} is already a clone of {url}, adding it to slip",
+                directory.display()
+            );
+            Ok(CloneDestination::SameRepo(directory))
+        }
+        CloneDestination::Occupied { directory, origin } => {
+            let alternate = alternate_directory(&directory);
+            let question = format!(
+                "{} already holds a different repository (origin: {}). clone into {} instead?",
+                directory.display(),
+                origin.as_deref().unwrap_or("none"),
+                alternate.display()
+            );
+            if confirm(&question)? {
+                Ok(CloneDestination::Empty(alternate))
+            } else {
+                Err(SlipError::Aborted)
+            }
+        }
+        empty => Ok(empty),
+    }
+}
+
+fn confirm(question: &str) -> io::Result<bool> {
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Query of the words passed to `list` and `status`.
+fn parse_query(words: &[String]) -> Result<Query, SlipError> {
+    Query::parse(&words.join(" ")).map_err(|err| SlipError::Usage(format!("invalid query: {err}")))
+}
+
+/// Hooks run after the command did its work, so their failures only make it fail at the end.
+fn check_hooks(failures: Vec<HookFailure>) -> Result<(), SlipError> {
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(SlipError::Hooks(failures))
+    }
+}
+
+/// Config at `--config`, an empty one if the file doesn't exist.
+fn get_config(cli: &Args) -> Result<Config, SlipError> {
+    let path = cli.config();
+    match fs::read_to_string(&path) {
+        Ok(content) => {
+            let config: Config =
+                toml::from_str(&content).map_err(|source| SlipError::ConfigParse {
+                    path: path.clone(),
+                    source,
+                })?;
+            tracing::debug!(path, ?config, "config loaded");
+            Ok(config)
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            tracing::debug!(path, "no config file, using defaults");
+            Ok(Config::new())
+        }
+        Err(err) => Err(err.into()),
+    }
 }
 
 fn get_profile(cli: &Args, config: &Config) -> WorkOrPersonal {
-    let category = if cli.personal {
+    if cli.personal {
         WorkOrPersonal::Personal
     } else if cli.work {
         WorkOrPersonal::Work
-    } else if config.default.is_some() {
-        config.default.unwrap()
+    } else if let Some(default) = config.default {
+        default
     } else {
         WorkOrPersonal::Personal
-    };
-    category
-}
-
-fn configure_git(repo: &Repo, config: &Config) -> Result<(), Box<dyn Error>> {
-    println!(
-        "configuring git for is {location} with {category}",
-        location = repo.location,
-        category = repo.category
-    );
-    let git_config: Option<GitConfig> = config.get_git_config(&repo.category);
-    Ok(if let Some(GitConfig { email, name }) = git_config {
-        if let Some(email) = email {
-            let git_email_command = format!("git config user.email {email}");
-            execute(git_email_command, Some(repo.location.as_ref()))?;
-        }
-        if let Some(name) = name {
-            let git_name_command = format!("git config user.name {name}");
-            execute(git_name_command, Some(repo.location.as_ref()))?;
-        }
-    })
+    }
 }
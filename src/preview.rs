@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::git;
+use crate::repolist::Repo;
+use crate::status::RepoStatus;
+
+const COMMITS: usize = 5;
+const README_LINES: usize = 12;
+
+/// What the preview pane of `slip ui` shows about a repo or worktree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preview {
+    pub status: RepoStatus,
+    /// `<hash> <subject> (<author>, <when>)`, newest first
+    pub commits: Vec<String>,
+    /// `user.name <user.email>` as git sees it in the repo
+    pub identity: Option<String>,
+    /// name and fetch url of every remote
+    pub remotes: Vec<(String, String)>,
+    /// first lines of the README, if there is one
+    pub readme: Vec<String>,
+}
+
+impl Preview {
+    /// Runs git in `location`, a checkout of `repo` (the repo itself or one of its worktrees).
+    pub fn collect(repo: &Repo, location: &str) -> Preview {
+        let dir = Path::new(location);
+        let status = RepoStatus::collect(&Repo {
+            location: location.to_string(),
+            ..repo.clone()
+        });
+        let commits = git::output(
+            dir,
+            &["log", &format!("-{COMMITS}"), "--format=%h %s (%an, %ar)"],
+        )
+        .map(|log| log.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+        let name = git::output(dir, &["config", "user.name"]);
+        let email = git::output(dir, &["config", "user.email"]);
+        let identity = match (name, email) {
+            (Some(name), Some(email)) => Some(format!("{name} <{email}>")),
+            (name, email) => name.or(email),
+        };
+        let remotes = git::output(dir, &["remote"])
+            .map(|remotes| {
+                remotes
+                    .lines()
+                    .map(|remote| {
+                        let url = git::remote_url(dir, remote).unwrap_or_default();
+                        (remote.to_string(), url)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Preview {
+            status,
+            commits,
+            identity,
+            remotes,
+            readme: readme(dir),
+        }
+    }
+}
+
+fn readme(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut readmes: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.to_lowercase().starts_with("readme"))
+        })
+        .collect();
+    // README.md before README.txt before ...
+    readmes.sort();
+    readmes
+        .first()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| {
+            content
+                .lines()
+                .take(README_LINES)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Collects previews on a background thread so the ui stays responsive while git runs.
+pub struct PreviewLoader {
+    requests: Sender<(Repo, String)>,
+    pub results: Receiver<(String, Preview)>,
+}
+
+impl PreviewLoader {
+    pub fn start() -> PreviewLoader {
+        let (requests, pending) = mpsc::channel::<(Repo, String)>();
+        let (done, results) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(mut request) = pending.recv() {
+                // only the latest selection matters when scrolling quickly
+                while let Ok(newer) = pending.try_recv() {
+                    request = newer;
+                }
+                let (repo, location) = request;
+                let preview = Preview::collect(&repo, &location);
+                if done.send((location, preview)).is_err() {
+                    break;
+                }
+            }
+        });
+        PreviewLoader { requests, results }
+    }
+
+    /// Asks for the preview of `location`, a checkout of `repo`; it arrives on `results`.
+    pub fn request(&self, repo: &Repo, location: &str) {
+        // the worker only stops once `self` is dropped
+        let _ = self.requests.send((repo.clone(), location.to_string()));
+    }
+}
+
+#[test]
+fn preview_of_repo() {
+    use crate::run;
+
+    let location = git::init_test_repo("preview");
+    let location_str = location.to_str().unwrap();
+    fs::write(location.join("README.md"), "# demo\n\nfirst line\n").unwrap();
+    for args in [
+        &["config", "user.name", "Jane Doe"][..],
+        &["config", "user.email", "jane@example.com"],
+        &["remote", "add", "origin", "git@example.com:acme/demo.git"],
+        &["add", "README.md"],
+        &["commit", "--quiet", "-m", "add readme"],
+    ] {
+        run("git", args, Some(location_str)).unwrap();
+    }
+    let repo = Repo {
+        url: "git@example.com:acme/demo.git".to_string(),
+        ..Repo::for_test("demo", location_str)
+    };
+
+    let preview = Preview::collect(&repo, location_str);
+    assert_eq!(Some("main"), preview.status.branch.as_deref());
+    assert!(!preview.status.dirty);
+    assert_eq!(1, preview.commits.len());
+    assert!(preview.commits[0].contains("add readme (Jane Doe,"));
+    assert_eq!(
+        Some("Jane Doe <jane@example.com>"),
+        preview.identity.as_deref()
+    );
+    assert_eq!(
+        vec![(
+            "origin".to_string(),
+            "git@example.com:acme/demo.git".to_string()
+        )],
+        preview.remotes
+    );
+    assert_eq!(vec!["# demo", "", "first line"], preview.readme);
+    fs::remove_dir_all(location).unwrap();
+}
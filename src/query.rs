@@ -0,0 +1,403 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+use crate::git;
+use crate::remote::RemoteUrl;
+use crate::repolist::Repo;
+
+/// Filter of `slip list`, `slip status` and the search box of `slip ui`.
+///
+/// Whitespace separated terms that all have to match:
+/// - `api`: fuzzy matches the name, location, url host or owner or a tag
+/// - `"my api"`: one of those contains the quoted text literally
+/// - `cat:work`, `host:gitlab`, `owner:acme`, `tag:infra`, `name:api`, `path:src`: the field
+///   contains the value, ignoring case; `tag:"infra"` the field is exactly the value
+/// - `dirty:yes` / `dirty:no`: the working tree has (no) uncommitted changes
+/// - `!term`: the term must not match; `!archived` drops repos containing `archived` anywhere
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Term {
+    negated: bool,
+    field: Option<Field>,
+    value: String,
+    /// quoted value, compared literally
+    exact: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Category,
+    Host,
+    Owner,
+    Tag,
+    Name,
+    Path,
+    Dirty,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        match name {
+            "cat" | "category" => Some(Field::Category),
+            "host" => Some(Field::Host),
+            "owner" => Some(Field::Owner),
+            "tag" => Some(Field::Tag),
+            "name" => Some(Field::Name),
+            "path" => Some(Field::Path),
+            "dirty" => Some(Field::Dirty),
+            _ => None,
+        }
+    }
+}
+
+/// Why a query couldn't be parsed, `column` counts characters from 1.
+#[derive(Debug, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    pub column: usize,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (column {})", self.message, self.column)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+fn error(message: impl Into<String>, index: usize) -> QueryError {
+    QueryError {
+        message: message.into(),
+        column: index + 1,
+    }
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut terms = vec![];
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            let negated = chars[i] == '!';
+            if negated {
+                i += 1;
+            }
+            let mut field = None;
+            let mut value = String::new();
+            let mut exact = false;
+            while i < chars.len() && !chars[i].is_whitespace() {
+                match chars[i] {
+                    '"' => {
+                        let close = chars[i + 1..]
+                            .iter()
+                            .position(|&c| c == '"')
+                            .ok_or_else(|| error("unterminated quote", i))?;
+                        value.extend(&chars[i + 1..i + 1 + close]);
+                        exact = true;
+                        i += close + 2;
+                    }
+                    ':' if field.is_none() && !exact && !value.is_empty() => {
+                        field = Some(Field::parse(&value).ok_or_else(|| {
+                            error(
+                                format!(
+                                    "unknown field `{value}:`, use cat, host, owner, tag, name, path or dirty"
+                                ),
+                                start,
+                            )
+                        })?);
+                        value.clear();
+                        i += 1;
+                    }
+                    c => {
+                        value.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            if value.is_empty() && !exact {
+                let message = match field {
+                    Some(_) => "field without a value",
+                    None => "`!` without a term to negate",
+                };
+                return Err(error(message, start));
+            }
+            if field == Some(Field::Dirty) && parse_bool(&value).is_none() {
+                return Err(error("dirty: takes yes or no", start));
+            }
+            terms.push(Term {
+                negated,
+                field,
+                value,
+                exact,
+            });
+        }
+        Ok(Query { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Whether matching depends on repos being dirty, which changes while browsing.
+    pub fn asks_dirty(&self) -> bool {
+        self.terms
+            .iter()
+            .any(|term| term.field == Some(Field::Dirty))
+    }
+
+    /// Score of `repo`, `None` if the query filters it out. `dirty` tells whether a repo has
+    /// uncommitted changes, `None` while that is unknown, which filters the repo out; it is
+    /// only asked for queries with `dirty:`.
+    pub fn score(
+        &self,
+        repo: &Repo,
+        matcher: &SkimMatcherV2,
+        dirty: &mut dyn FnMut(&Repo) -> Option<bool>,
+    ) -> Option<i64> {
+        let dirty = if self.asks_dirty() {
+            Some(dirty(repo)?)
+        } else {
+            None
+        };
+        let fields = Fields::new(repo);
+        let mut total = 0;
+        for term in &self.terms {
+            match (term.score(&fields, matcher, dirty), term.negated) {
+                (Some(score), false) => total += score,
+                (None, true) => {}
+                _ => return None,
+            }
+        }
+        Some(total)
+    }
+
+    /// Whether `repo` matches, running git to find out if it is dirty when needed.
+    pub fn matches(&self, repo: &Repo, matcher: &SkimMatcherV2) -> bool {
+        self.score(repo, matcher, &mut |repo| {
+            Some(git::is_dirty(Path::new(&repo.location)))
+        })
+        .is_some()
+    }
+
+    /// Free text of the query, the parts worth highlighting in matched repos.
+    pub fn text_terms(&self) -> Vec<&str> {
+        self.terms
+            .iter()
+            .filter(|term| !term.negated && term.field.is_none())
+            .map(|term| term.value.as_str())
+            .collect()
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" | "true" | "y" | "1" => Some(true),
+        "no" | "false" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Texts of a repo terms are matched against.
+struct Fields<'a> {
+    name: &'a str,
+    alias: Option<&'a str>,
+    path: &'a str,
+    category: String,
+    remote: Option<RemoteUrl>,
+    tags: &'a [String],
+}
+
+impl Fields<'_> {
+    fn new(repo: &Repo) -> Fields<'_> {
+        Fields {
+            name: &repo.name,
+            alias: repo.alias.as_deref(),
+            path: &repo.location,
+            category: repo.category.to_string(),
+            remote: RemoteUrl::parse(&repo.url),
+            tags: &repo.tags,
+        }
+    }
+
+    fn values(&self, field: Field) -> Vec<&str> {
+        match field {
+            Field::Category => vec![&self.category],
+            Field::Host => self
+                .remote
+                .iter()
+                .map(|remote| remote.host.as_str())
+                .collect(),
+            Field::Owner => self.remote.iter().filter_map(RemoteUrl::owner).collect(),
+            Field::Tag => self.tags.iter().map(String::as_str).collect(),
+            Field::Name => [self.name].into_iter().chain(self.alias).collect(),
+            Field::Path => vec![self.path],
+            Field::Dirty => vec![],
+        }
+    }
+
+    /// Fields free text is matched against, with the weight of a fuzzy match;
+    /// name matches count double to rank them first.
+    fn free_text(&self) -> Vec<(&str, i64)> {
+        [
+            (Field::Name, 2),
+            (Field::Path, 1),
+            (Field::Host, 1),
+            (Field::Owner, 1),
+            (Field::Tag, 1),
+        ]
+        .into_iter()
+        .flat_map(|(field, weight)| {
+            self.values(field)
+                .into_iter()
+                .map(move |value| (value, weight))
+        })
+        .collect()
+    }
+}
+
+impl Term {
+    /// Score if the term (ignoring negation) matches.
+    fn score(&self, fields: &Fields, matcher: &SkimMatcherV2, dirty: Option<bool>) -> Option<i64> {
+        let contains = |value: &str| value.to_lowercase().contains(&self.value.to_lowercase());
+        let found = match self.field {
+            Some(Field::Dirty) => dirty.is_some() && parse_bool(&self.value) == dirty,
+            Some(field) if self.exact => fields.values(field).contains(&self.value.as_str()),
+            Some(field) => fields.values(field).into_iter().any(contains),
+            None if self.exact => fields
+                .free_text()
+                .iter()
+                .any(|(value, _)| value.contains(&self.value)),
+            // a negated word excludes what contains it, fuzzy matching would exclude far too much
+            None if self.negated => fields.free_text().iter().any(|(value, _)| contains(value)),
+            None => {
+                return fields
+                    .free_text()
+                    .iter()
+                    .filter_map(|(value, weight)| {
+                        Some(matcher.fuzzy_match(value, &self.value)? * weight)
+                    })
+                    .max()
+            }
+        };
+        found.then_some(0)
+    }
+}
+
+#[test]
+fn parse_queries() {
+    let term = |negated, field, value: &str, exact| Term {
+        negated,
+        field,
+        value: value.to_string(),
+        exact,
+    };
+    assert_eq!(Query::default(), Query::parse("  ").unwrap());
+    assert_eq!(
+        Query {
+            terms: vec![
+                term(false, Some(Field::Category), "work", false),
+                term(false, None, "api", false),
+                term(true, Some(Field::Tag), "archived repo", true),
+                term(true, None, "old", false),
+                term(false, None, "a:b", true),
+            ]
+        },
+        Query::parse(r#"cat:work api !tag:"archived repo" !old "a:b""#).unwrap()
+    );
+    assert_eq!(
+        Err(error(
+            "unknown field `foo:`, use cat, host, owner, tag, name, path or dirty",
+            4
+        )),
+        Query::parse("api foo:bar")
+    );
+    assert_eq!(
+        Err(error("unterminated quote", 4)),
+        Query::parse(r#"tag:"infra"#)
+    );
+    assert_eq!(
+        Err(error("field without a value", 0)),
+        Query::parse("host:")
+    );
+    assert_eq!(
+        Err(error("`!` without a term to negate", 0)),
+        Query::parse("! api")
+    );
+    assert_eq!(
+        Err(error("dirty: takes yes or no", 0)),
+        Query::parse("dirty:maybe")
+    );
+}
+
+#[test]
+fn filter_repos() {
+    use crate::config::WorkOrPersonal;
+
+    let repo = |name: &str, url: &str, category, tags: &[&str]| Repo {
+        url: url.to_string(),
+        category,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..Repo::for_test(name, &format!("/src/{name}"))
+    };
+    let mut repos = [
+        repo(
+            "api",
+            "git@gitlab.com:acme/api.git",
+            WorkOrPersonal::Work,
+            &["infra"],
+        ),
+        repo(
+            "site",
+            "https://github.com/me/site",
+            WorkOrPersonal::Personal,
+            &["archived"],
+        ),
+    ];
+    repos[1].alias = Some("homepage".to_string());
+    let matcher = SkimMatcherV2::default();
+    let names = |query: &str| {
+        let query = Query::parse(query).unwrap();
+        repos
+            .iter()
+            .filter(|repo| {
+                query
+                    .score(repo, &matcher, &mut |repo| match repo.name.as_str() {
+                        "site" => Some(true),
+                        "api" => Some(false),
+                        _ => None,
+                    })
+                    .is_some()
+            })
+            .map(|repo| repo.name.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["api", "site"], names(""));
+    assert_eq!(vec!["api"], names("cat:work"));
+    assert_eq!(vec!["api"], names("host:GitLab"));
+    assert_eq!(vec!["api"], names("owner:acme"));
+    assert_eq!(vec!["site"], names("owner:\"me\""));
+    assert_eq!(vec!["api"], names("tag:infra"));
+    assert_eq!(vec!["api"], names("!archived"));
+    assert_eq!(vec!["site"], names("dirty:yes"));
+    assert_eq!(vec!["api"], names("!dirty:yes"));
+    assert_eq!(Vec::<&str>::new(), names(r#"tag:"infr""#));
+    assert_eq!(vec!["api"], names(r#""acme""#));
+    assert_eq!(Vec::<&str>::new(), names(r#""Acme""#));
+    assert_eq!(vec!["api"], names("acme api"));
+    assert_eq!(vec!["site"], names("name:homepage"));
+    // a repo whose status isn't known yet matches no `dirty:` term, negated or not
+    let query = Query::parse("!dirty:yes").unwrap();
+    assert_eq!(None, query.score(&repos[0], &matcher, &mut |_| None));
+}
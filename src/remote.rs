@@ -0,0 +1,210 @@
+use std::fmt::Display;
+
+use url::Url;
+
+use crate::config::UrlRewrite;
+
+/// Host and repository path of a git remote, independent of how it was written
+/// (`https://host/owner/repo.git`, `ssh://git@host/owner/repo`, `git@host:owner/repo`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteUrl {
+    pub host: String,
+    /// path without leading `/` and trailing `.git`, for example `owner/repo`
+    pub path: String,
+}
+
+impl RemoteUrl {
+    pub fn parse(url: &str) -> Option<RemoteUrl> {
+        let url = url.trim();
+        let (host, path) = if url.contains("://") {
+            let parsed = Url::parse(url).ok()?;
+            if parsed.scheme() == "file" {
+                return None;
+            }
+            (parsed.host_str()?.to_string(), parsed.path().to_string())
+        } else {
+            // scp like syntax: [user@]host:owner/repo
+            let (host, path) = url.split_once(':')?;
+            // `C:\repo` is a windows path, not a host
+            if host.len() < 2 || host.contains(['/', '\\']) || path.starts_with("//") {
+                return None;
+            }
+            let host = host.rsplit('@').next().unwrap_or(host);
+            (host.to_string(), path.to_string())
+        };
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        if host.is_empty() || path.is_empty() {
+            return None;
+        }
+        Some(RemoteUrl {
+            host: host.to_lowercase(),
+            path: path.to_string(),
+        })
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.path.rsplit_once('/').map(|(owner, _)| owner)
+    }
+
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+impl Display for RemoteUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.host, self.path)
+    }
+}
+
+/// Url to hand to git: the longest matching `instead_of` prefix replaced by its `url`.
+pub fn rewrite_url(url: &str, rewrites: &[UrlRewrite]) -> String {
+    rewrites
+        .iter()
+        .filter(|rewrite| url.starts_with(&rewrite.instead_of))
+        .rev()
+        .max_by_key(|rewrite| rewrite.instead_of.len())
+        .map(|rewrite| format!("{}{}", rewrite.url, &url[rewrite.instead_of.len()..]))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Inverse of `rewrite_url`: a url written with a host alias mapped back to the real host.
+pub fn canonical_url(url: &str, rewrites: &[UrlRewrite]) -> String {
+    rewrites
+        .iter()
+        .filter(|rewrite| url.starts_with(&rewrite.url))
+        // on ties the first rule wins, `max_by_key` keeps the last one
+        .rev()
+        .max_by_key(|rewrite| rewrite.url.len())
+        .map(|rewrite| format!("{}{}", rewrite.instead_of, &url[rewrite.url.len()..]))
+        .unwrap_or_else(|| url.to_string())
+}
+
+#[test]
+fn rewrite_host_aliases() {
+    let rewrites = [
+        UrlRewrite {
+            url: "git@github-work:".to_string(),
+            instead_of: "git@github.com:".to_string(),
+        },
+        UrlRewrite {
+            url: "git@github-work:".to_string(),
+            instead_of: "https://github.com/".to_string(),
+        },
+    ];
+    assert_eq!(
+        "git@github-work:acme/api.git",
+        rewrite_url("git@github.com:acme/api.git", &rewrites)
+    );
+    assert_eq!(
+        "git@github-work:acme/api",
+        rewrite_url("https://github.com/acme/api", &rewrites)
+    );
+    assert_eq!(
+        "git@gitlab.com:acme/api",
+        rewrite_url("git@gitlab.com:acme/api", &rewrites)
+    );
+    assert_eq!(
+        "git@github.com:acme/api.git",
+        canonical_url("git@github-work:acme/api.git", &rewrites)
+    );
+}
+
+/// Whether both urls point to the same repository, even when one is written as
+/// https and the other as ssh. Urls that are not remotes (local paths) are
+/// compared literally.
+pub fn same_remote(left: &str, right: &str) -> bool {
+    match (RemoteUrl::parse(left), RemoteUrl::parse(right)) {
+        (Some(left), Some(right)) => left == right,
+        (None, None) => {
+            let normalize = |url: &str| {
+                let url = url.trim().trim_end_matches('/');
+                url.strip_suffix(".git").unwrap_or(url).to_string()
+            };
+            normalize(left) == normalize(right)
+        }
+        _ => false,
+    }
+}
+
+/// Expands `owner/repo` to a url on the same host and in the same format as `origin`.
+/// Anything that already is a url is returned as is.
+pub fn expand_shorthand(origin: &str, url_or_shorthand: &str) -> Option<String> {
+    if RemoteUrl::parse(url_or_shorthand).is_some()
+        || url_or_shorthand.contains(':')
+        || url_or_shorthand.starts_with(['/', '.', '~'])
+    {
+        return Some(url_or_shorthand.to_string());
+    }
+    let shorthand = url_or_shorthand.trim_matches('/');
+    let shorthand = shorthand.strip_suffix(".git").unwrap_or(shorthand);
+    if !shorthand.contains('/') {
+        return None;
+    }
+    let origin_path = RemoteUrl::parse(origin)?.path;
+    let at = origin.rfind(&origin_path)?;
+    Some(format!(
+        "{}{}{}",
+        &origin[..at],
+        shorthand,
+        &origin[at + origin_path.len()..]
+    ))
+}
+
+#[test]
+fn expand_upstream_shorthand() {
+    assert_eq!(
+        Some("git@github.com:rust-lang/cargo.git".to_string()),
+        expand_shorthand("git@github.com:cedric05/cargo.git", "rust-lang/cargo")
+    );
+    assert_eq!(
+        Some("https://gitlab.com/group/sub/app".to_string()),
+        expand_shorthand("https://gitlab.com/me/app", "group/sub/app")
+    );
+    assert_eq!(
+        Some("https://github.com/rust-lang/cargo".to_string()),
+        expand_shorthand(
+            "git@github.com:me/cargo",
+            "https://github.com/rust-lang/cargo"
+        )
+    );
+    assert_eq!(None, expand_shorthand("git@github.com:me/cargo", "cargo"));
+    assert_eq!(None, expand_shorthand("/tmp/cargo", "rust-lang/cargo"));
+}
+
+#[test]
+fn parse_remote_urls() {
+    let expected = RemoteUrl {
+        host: "github.com".to_string(),
+        path: "gitignore/gitignore".to_string(),
+    };
+    for url in [
+        "https://github.com/gitignore/gitignore",
+        "https://github.com/gitignore/gitignore.git",
+        "https://GitHub.com/gitignore/gitignore/",
+        "ssh://git@github.com/gitignore/gitignore.git",
+        "git@github.com:gitignore/gitignore.git",
+        "github.com:gitignore/gitignore",
+    ] {
+        assert_eq!(Some(expected.clone()), RemoteUrl::parse(url), "{url}");
+    }
+    assert_eq!(Some("gitignore"), expected.owner());
+    assert_eq!("gitignore", expected.name());
+    assert_eq!(None, RemoteUrl::parse("/home/user/projects/slip"));
+    assert_eq!(None, RemoteUrl::parse("file:///home/user/projects/slip"));
+}
+
+#[test]
+fn compare_remotes() {
+    assert!(same_remote(
+        "https://github.com/cedric05/slip.git",
+        "git@github.com:cedric05/slip"
+    ));
+    assert!(!same_remote(
+        "https://github.com/cedric05/slip.git",
+        "git@github.com:cedric05/dothttp"
+    ));
+    assert!(same_remote("/tmp/slip/", "/tmp/slip"));
+    assert!(!same_remote("/tmp/slip", "git@github.com:cedric05/slip"));
+}
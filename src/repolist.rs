@@ -1,17 +1,67 @@
 use super::WorkOrPersonal;
+use crate::SlipError;
 use serde_derive::{Deserialize, Serialize};
-use std::error::Error;
 use std::fmt::Display;
 use std::fs;
+use std::io;
 
 const DEFAULT_REPOS_CONFIG_LOCATION: &str = "~/.slip.repos.toml";
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Repo {
     pub url: String,
     pub location: String,
     pub name: String,
     pub category: WorkOrPersonal,
+    /// repository this one is a fork of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    /// free form labels the repo can be searched by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub worktrees: Vec<Worktree>,
+    /// name `slip ui` lists the repo under, set by renaming it there; `name` stays what
+    /// commands find it by
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Worktree {
+    pub branch: String,
+    pub location: String,
+}
+
+impl Repo {
+    /// Work repo `name` at `location` without url, tags or worktrees.
+    #[cfg(test)]
+    pub(crate) fn for_test(name: &str, location: &str) -> Repo {
+        Repo {
+            url: String::new(),
+            location: location.to_string(),
+            name: name.to_string(),
+            category: WorkOrPersonal::Work,
+            upstream: None,
+            tags: vec![],
+            worktrees: vec![],
+            alias: None,
+        }
+    }
+
+    /// `alias` if it has one, else `name`.
+    pub fn display_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Adds the `tags` it doesn't have yet.
+    pub fn add_tags(&mut self, tags: &[String]) {
+        for tag in tags {
+            if !self.tags.contains(tag) {
+                self.tags.push(tag.clone());
+            }
+        }
+    }
 }
 
 impl Display for Repo {
@@ -31,17 +81,192 @@ pub struct RepoList {
 }
 
 impl RepoList {
-    pub fn get_config() -> Result<RepoList, Box<dyn Error>> {
+    pub fn get_config() -> Result<RepoList, SlipError> {
         let cloned_repos_list_location = shellexpand::tilde(DEFAULT_REPOS_CONFIG_LOCATION);
-        let repos: Self = fs::read_to_string(cloned_repos_list_location.as_ref())
-            .map(|x| toml::from_str(&x))
-            .unwrap_or_else(|_| Ok(RepoList { repos: vec![] }))?;
-        Ok(repos)
+        let path = cloned_repos_list_location.to_string();
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                toml::from_str(&content).map_err(|source| SlipError::RegistryParse { path, source })
+            }
+            // nothing registered yet
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(RepoList { repos: vec![] }),
+            Err(source) => Err(SlipError::RegistryIo { path, source }),
+        }
+    }
+
+    /// Repo registered under `name_or_location`, by name first, then by location.
+    pub fn find(&self, name_or_location: &str) -> Option<&Repo> {
+        self.position(name_or_location)
+            .map(|position| &self.repos[position])
+    }
+
+    pub fn find_mut(&mut self, name_or_location: &str) -> Option<&mut Repo> {
+        self.position(name_or_location)
+            .map(|position| &mut self.repos[position])
+    }
+
+    fn position(&self, name_or_location: &str) -> Option<usize> {
+        let location = name_or_location.trim_end_matches(['/', '\\']);
+        self.repos
+            .iter()
+            .position(|repo| repo.name == name_or_location)
+            .or_else(|| {
+                self.repos
+                    .iter()
+                    .position(|repo| repo.location.trim_end_matches(['/', '\\']) == location)
+            })
     }
 
-    pub fn save_config(self: &Self) {
+    pub fn save_config(&self) -> Result<(), SlipError> {
+        if let Some(diff) = self.save_or_diff()? {
+            println!(
+                "would update {}:",
+                shellexpand::tilde(DEFAULT_REPOS_CONFIG_LOCATION)
+            );
+            print!("{diff}");
+        }
+        Ok(())
+    }
+
+    /// Writes the registry, or in dry-run mode returns the diff it would apply instead of
+    /// printing it, for `slip ui` to show.
+    pub fn save_or_diff(&self) -> Result<Option<String>, SlipError> {
         let cloned_repos_list_location = shellexpand::tilde(DEFAULT_REPOS_CONFIG_LOCATION);
-        let dump = toml::to_vec(&self).unwrap();
-        fs::write(cloned_repos_list_location.as_ref(), dump).unwrap();
+        let path = cloned_repos_list_location.to_string();
+        let dump = toml::to_string(&self).map_err(|err| SlipError::RegistryIo {
+            path: path.clone(),
+            source: io::Error::new(io::ErrorKind::InvalidData, err),
+        })?;
+        if crate::is_dry_run() {
+            let current = fs::read_to_string(&path).unwrap_or_default();
+            return Ok(Some(diff_lines(&current, &dump)));
+        }
+        fs::write(&path, &dump).map_err(|source| SlipError::RegistryIo {
+            path: path.clone(),
+            source,
+        })?;
+        tracing::info!(path, repos = self.repos.len(), "registry saved");
+        Ok(None)
+    }
+}
+
+/// Line diff of `old` and `new`: removed lines prefixed with `-`, added ones with `+`
+/// and up to `CONTEXT` unchanged lines around them with a space; skipped
+/// unchanged lines are shown as `...`.
+fn diff_lines(old: &str, new: &str) -> String {
+    const CONTEXT: usize = 3;
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // only the changed middle goes through the quadratic LCS, edits to a large registry
+    // usually touch a few neighbouring lines
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let mut lines: Vec<(char, &str)> = old[..prefix].iter().map(|line| (' ', *line)).collect();
+    lines.extend(diff_middle(
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    ));
+    lines.extend(old[old.len() - suffix..].iter().map(|line| (' ', *line)));
+
+    let mut shown = vec![false; lines.len()];
+    for (at, (mark, _)) in lines.iter().enumerate() {
+        if *mark != ' ' {
+            let end = (at + CONTEXT + 1).min(lines.len());
+            shown[at.saturating_sub(CONTEXT)..end].fill(true);
+        }
+    }
+    let mut diff = String::new();
+    let mut skipped = false;
+    for ((mark, line), shown) in lines.iter().zip(shown) {
+        if shown {
+            if skipped {
+                diff.push_str("...\n");
+                skipped = false;
+            }
+            diff.push_str(&format!("{mark}{line}\n"));
+        } else {
+            skipped = true;
+        }
+    }
+    diff
+}
+
+/// Lines of the smallest diff between `old` and `new`, or all of `old` removed and all of `new`
+/// added when they are too large for the LCS table.
+fn diff_middle<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str)> {
+    const MAX_CELLS: usize = 4_000_000;
+    if old.len().saturating_mul(new.len()) > MAX_CELLS {
+        return old
+            .iter()
+            .map(|line| ('-', *line))
+            .chain(new.iter().map(|line| ('+', *line)))
+            .collect();
+    }
+    // longest common subsequence lengths of all suffixes
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
     }
+    lines
+}
+
+#[test]
+fn diff_registry() {
+    assert_eq!(
+        " [[repos]]\n-name = \"a\"\n+name = \"b\"\n category = \"Work\"\n+tags = []\n",
+        diff_lines(
+            "[[repos]]\nname = \"a\"\ncategory = \"Work\"\n",
+            "[[repos]]\nname = \"b\"\ncategory = \"Work\"\ntags = []\n"
+        )
+    );
+    assert_eq!("+a\n", diff_lines("", "a"));
+    assert_eq!(
+        "...\n 2\n 3\n 4\n+5\n",
+        diff_lines("0\n1\n2\n3\n4\n", "0\n1\n2\n3\n4\n5\n")
+    );
+    assert_eq!("", diff_lines("a\nb\n", "a\nb\n"));
+
+    // a registry of 10k repos with one of them renamed
+    let registry = |renamed: &str| {
+        (0..10_000)
+            .map(|repo| match repo {
+                5000 => format!("name = \"{renamed}\"\n"),
+                repo => format!("name = \"repo{repo}\"\n"),
+            })
+            .collect::<String>()
+    };
+    assert_eq!(
+        "...\n name = \"repo4997\"\n name = \"repo4998\"\n name = \"repo4999\"\n-name = \"repo5000\"\n+name = \"api\"\n name = \"repo5001\"\n name = \"repo5002\"\n name = \"repo5003\"\n",
+        diff_lines(&registry("repo5000"), &registry("api"))
+    );
 }
@@ -0,0 +1,55 @@
+use clap::ArgEnum;
+
+/// Shells `slip shell-init` has a wrapper for.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// A process can't change the directory of the shell that started it, so the
+/// wrapper has `slip ui` write the picked path to a file and changes into it.
+const POSIX: &str = r#"slip() {
+    local slip_path slip_status slip_dir
+    slip_path="$(mktemp)" || return
+    command slip --path-file "$slip_path" "$@"
+    slip_status=$?
+    slip_dir="$(cat -- "$slip_path")"
+    rm -f -- "$slip_path"
+    if [ -n "$slip_dir" ] && [ -d "$slip_dir" ]; then
+        cd -- "$slip_dir" || return
+    fi
+    return $slip_status
+}
+"#;
+
+const FISH: &str = r#"function slip --wraps slip --description 'slip, changing into the repo picked in slip ui'
+    set -l slip_path (mktemp); or return
+    command slip --path-file $slip_path $argv
+    set -l slip_status $status
+    set -l slip_dir (cat -- $slip_path)
+    rm -f -- $slip_path
+    if test -n "$slip_dir" -a -d "$slip_dir"
+        cd $slip_dir
+    end
+    return $slip_status
+end
+"#;
+
+impl Shell {
+    /// Definition of the `slip` wrapper function for the shell.
+    pub fn script(&self) -> &'static str {
+        match self {
+            Shell::Bash | Shell::Zsh => POSIX,
+            Shell::Fish => FISH,
+        }
+    }
+}
+
+#[test]
+fn wrappers_pass_the_path_file() {
+    for shell in Shell::value_variants() {
+        assert!(shell.script().contains("command slip --path-file"));
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::git;
+use crate::repolist::Repo;
+use crate::SlipError;
+
+const STATUS_CACHE_LOCATION: &str = "~/.slip.status.toml";
+
+/// Working tree state of a registered repo.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoStatus {
+    /// checked out branch, `None` on a detached HEAD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub dirty: bool,
+    /// commits ahead of / behind the branch's tracking branch
+    pub ahead: usize,
+    pub behind: usize,
+    /// commits the default branch of `upstream` has that HEAD doesn't, for forks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub behind_upstream: Option<usize>,
+    /// entries of `git stash list`
+    #[serde(default)]
+    pub stashes: usize,
+}
+
+impl RepoStatus {
+    pub fn collect(repo: &Repo) -> RepoStatus {
+        let dir = Path::new(&repo.location);
+        let branch = git::current_branch(dir);
+        let dirty = git::is_dirty(dir);
+        let (ahead, behind) = git::output(
+            dir,
+            &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"],
+        )
+        .and_then(|counts| {
+            let (ahead, behind) = counts.split_once(char::is_whitespace)?;
+            Some((ahead.trim().parse().ok()?, behind.trim().parse().ok()?))
+        })
+        .unwrap_or((0, 0));
+        let behind_upstream = repo.upstream.as_ref().and_then(|_| {
+            let upstream_head = git::remote_head(dir, "upstream")?;
+            git::count_commits(dir, "HEAD", &upstream_head)
+        });
+        let stashes = git::output(dir, &["stash", "list"])
+            .map(|stashes| stashes.lines().count())
+            .unwrap_or(0);
+        RepoStatus {
+            branch,
+            dirty,
+            ahead,
+            behind,
+            behind_upstream,
+            stashes,
+        }
+    }
+}
+
+impl Display for RepoStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.branch.as_deref().unwrap_or("(detached)"))?;
+        if self.dirty {
+            write!(f, " *")?;
+        }
+        if self.ahead > 0 {
+            write!(f, " ↑{}", self.ahead)?;
+        }
+        if self.behind > 0 {
+            write!(f, " ↓{}", self.behind)?;
+        }
+        if self.stashes > 0 {
+            write!(f, " ≡{}", self.stashes)?;
+        }
+        if let Some(behind_upstream) = self.behind_upstream {
+            write!(f, ", {behind_upstream} behind upstream")?;
+        }
+        Ok(())
+    }
+}
+
+/// Statuses of the last `slip ui` by location, shown until fresh ones are collected.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusCache {
+    pub statuses: BTreeMap<String, RepoStatus>,
+}
+
+impl StatusCache {
+    /// The cache, empty if there is none or it can't be read.
+    pub fn load() -> StatusCache {
+        let path = shellexpand::tilde(STATUS_CACHE_LOCATION).to_string();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return StatusCache::default();
+        };
+        toml::from_str(&content).unwrap_or_else(|err| {
+            tracing::debug!(path, %err, "ignoring unreadable status cache");
+            StatusCache::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), SlipError> {
+        if crate::is_dry_run() {
+            return Ok(());
+        }
+        let path = shellexpand::tilde(STATUS_CACHE_LOCATION).to_string();
+        let dump = toml::to_string(self)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        fs::write(&path, dump)?;
+        tracing::debug!(path, statuses = self.statuses.len(), "status cache saved");
+        Ok(())
+    }
+}
+
+/// Collects the status of checkouts one after another on a background thread.
+pub struct StatusLoader {
+    /// location and status, in the order the checkouts were given
+    pub results: Receiver<(String, RepoStatus)>,
+}
+
+impl StatusLoader {
+    /// Starts with `checkouts`, each a repo and the location of the repo or one of its worktrees.
+    pub fn start(checkouts: Vec<(Repo, String)>) -> StatusLoader {
+        let (done, results) = mpsc::channel();
+        thread::spawn(move || {
+            for (repo, location) in checkouts {
+                let status = RepoStatus::collect(&Repo {
+                    location: location.clone(),
+                    ..repo
+                });
+                if done.send((location, status)).is_err() {
+                    break;
+                }
+            }
+        });
+        StatusLoader { results }
+    }
+}
+
+#[test]
+fn status_cache_round_trip() {
+    let mut cache = StatusCache::default();
+    cache.statuses.insert(
+        "/src/api".to_string(),
+        RepoStatus {
+            branch: Some("main".to_string()),
+            dirty: true,
+            ahead: 1,
+            behind: 2,
+            behind_upstream: None,
+            stashes: 3,
+        },
+    );
+    cache
+        .statuses
+        .insert("/src/site".to_string(), RepoStatus::default());
+    let dump = toml::to_string(&cache).unwrap();
+    assert_eq!(cache, toml::from_str(&dump).unwrap());
+    assert_eq!("main * ↑1 ↓2 ≡3", cache.statuses["/src/api"].to_string());
+}
@@ -0,0 +1,188 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `.gitignore` files bundled for `slip new --gitignore <name>`.
+pub const GITIGNORES: &[(&str, &str)] = &[
+    ("Go", include_str!("../templates/gitignore/Go.gitignore")),
+    (
+        "Java",
+        include_str!("../templates/gitignore/Java.gitignore"),
+    ),
+    (
+        "Node",
+        include_str!("../templates/gitignore/Node.gitignore"),
+    ),
+    (
+        "Python",
+        include_str!("../templates/gitignore/Python.gitignore"),
+    ),
+    (
+        "Rust",
+        include_str!("../templates/gitignore/Rust.gitignore"),
+    ),
+];
+
+/// Licenses bundled for `slip new --license <name>`, `{{year}}` and `{{author_name}}` are substituted.
+pub const LICENSES: &[(&str, &str)] = &[
+    (
+        "Apache-2.0",
+        include_str!("../templates/license/Apache-2.0.txt"),
+    ),
+    (
+        "BSD-3-Clause",
+        include_str!("../templates/license/BSD-3-Clause.txt"),
+    ),
+    ("MIT", include_str!("../templates/license/MIT.txt")),
+    (
+        "Unlicense",
+        include_str!("../templates/license/Unlicense.txt"),
+    ),
+];
+
+/// Bundled file `name` of `files`, matched case insensitively.
+pub fn bundled(files: &[(&str, &'static str)], name: &str) -> Option<&'static str> {
+    files
+        .iter()
+        .find(|(bundled, _)| bundled.eq_ignore_ascii_case(name))
+        .map(|(_, content)| *content)
+}
+
+/// Current year in UTC, for copyright lines.
+pub fn current_year() -> i64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / 86400)
+        .unwrap_or_default() as i64;
+    // civil_from_days of Howard Hinnant's date algorithms
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let month = (5 * doy + 2) / 153;
+    let year = yoe + era * 400;
+    if month >= 10 {
+        year + 1
+    } else {
+        year
+    }
+}
+
+/// Replaces every `{{key}}` of `variables` in `text`, in one pass so that
+/// `{{...}}` inside a value is kept as it is.
+pub fn substitute(text: &str, variables: &[(&str, &str)]) -> String {
+    let mut substituted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        substituted.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let variable = variables.iter().find_map(|(key, value)| {
+            let after = rest[2..].strip_prefix(key)?.strip_prefix("}}")?;
+            Some((value, after))
+        });
+        match variable {
+            Some((value, after)) => {
+                substituted.push_str(value);
+                rest = after;
+            }
+            None => {
+                substituted.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    substituted.push_str(rest);
+    substituted
+}
+
+/// Copies the template directory `source` into `destination`, substituting
+/// `variables` in file names and in the content of text files. `.git` of the
+/// template is not copied.
+pub fn render(source: &Path, destination: &Path, variables: &[(&str, &str)]) -> io::Result<()> {
+    fs::create_dir_all(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let name = substitute(&entry.file_name().to_string_lossy(), variables);
+        let target = destination.join(name);
+        if entry.file_type()?.is_dir() {
+            render(&entry.path(), &target, variables)?;
+        } else {
+            let content = fs::read(entry.path())?;
+            match String::from_utf8(content) {
+                Ok(text) => fs::write(&target, substitute(&text, variables))?,
+                // binary files are copied as they are
+                Err(err) => fs::write(&target, err.into_bytes())?,
+            }
+            fs::set_permissions(&target, entry.metadata()?.permissions())?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn bundled_files() {
+    assert!(bundled(GITIGNORES, "rust").unwrap().contains("/target/"));
+    assert!(bundled(LICENSES, "mit")
+        .unwrap()
+        .contains("{{author_name}}"));
+    assert_eq!(None, bundled(LICENSES, "GPL-3.0"));
+    assert!(current_year() >= 2024);
+}
+
+#[test]
+fn substitute_variables() {
+    assert_eq!(
+        "name = \"api-svc\" # {{unknown}} work",
+        substitute(
+            "name = \"{{name}}\" # {{unknown}} {{profile}}",
+            &[("name", "api-svc"), ("profile", "work")]
+        )
+    );
+    // a value isn't searched for variables again
+    assert_eq!(
+        "{{profile}} in work",
+        substitute(
+            "{{name}} in {{profile}}",
+            &[("name", "{{profile}}"), ("profile", "work")]
+        )
+    );
+}
+
+#[test]
+fn render_template_directory() {
+    let root = std::env::temp_dir().join(format!("slip-template-{}", std::process::id()));
+    let source = root.join("template");
+    fs::create_dir_all(source.join("src")).unwrap();
+    fs::create_dir_all(source.join(".git")).unwrap();
+    fs::write(source.join("Cargo.toml"), "name = \"{{name}}\"\n").unwrap();
+    fs::write(
+        source.join("src").join("{{name}}.rs"),
+        "// {{author_email}}\n",
+    )
+    .unwrap();
+    fs::write(source.join("logo.bin"), [0xff, 0xfe, 0x00]).unwrap();
+
+    let destination = root.join("api-svc");
+    let variables = [("name", "api_svc"), ("author_email", "dev@example.com")];
+    render(&source, &destination, &variables).unwrap();
+
+    assert_eq!(
+        "name = \"api_svc\"\n",
+        fs::read_to_string(destination.join("Cargo.toml")).unwrap()
+    );
+    assert_eq!(
+        "// dev@example.com\n",
+        fs::read_to_string(destination.join("src").join("api_svc.rs")).unwrap()
+    );
+    assert_eq!(
+        vec![0xff, 0xfe, 0x00],
+        fs::read(destination.join("logo.bin")).unwrap()
+    );
+    assert!(!destination.join(".git").exists());
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,161 @@
+use tui::style::{Color, Modifier, Style};
+
+use crate::config::{ThemeConfig, ThemePreset};
+
+/// Colors and layout of `slip ui`, from `[ui.theme]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// entries of the list, and those marked for bulk actions
+    pub item: Style,
+    pub marked: Style,
+    /// the selected entry, drawn over its style
+    pub selected: Style,
+    pub path: Style,
+    /// characters matching the search, drawn over the text's style
+    pub matched: Style,
+    /// the dot in front of marked entries
+    pub mark: Style,
+    pub branch: Style,
+    pub dirty: Style,
+    pub ahead_behind: Style,
+    pub stashes: Style,
+    /// outcomes in the bulk and clone panels and dialogs
+    pub pending: Style,
+    pub success: Style,
+    pub error: Style,
+    /// percent of the width the list takes, of the height the filter and the cheatsheet take
+    pub list_width: u16,
+    pub filter_height: u16,
+    pub cheatsheet_height: u16,
+    /// lines each entry of the list takes, 1 or 2
+    pub item_height: usize,
+}
+
+impl Theme {
+    /// The theme `theme` configures; `no_color` (see [`no_color`]) picks `NoColor`
+    /// unless a preset is set.
+    pub fn new(theme: Option<&ThemeConfig>, no_color: bool) -> Theme {
+        let theme = theme.cloned().unwrap_or_default();
+        let preset = match theme.preset {
+            Some(preset) => preset,
+            None if no_color => ThemePreset::NoColor,
+            None => ThemePreset::Light,
+        };
+        Theme {
+            list_width: theme.list_width.unwrap_or(55).clamp(10, 100),
+            filter_height: theme.filter_height.unwrap_or(10).min(40),
+            cheatsheet_height: theme.cheatsheet_height.unwrap_or(5).min(40),
+            item_height: if theme.one_line.unwrap_or(false) {
+                1
+            } else {
+                2
+            },
+            ..Theme::preset(preset)
+        }
+    }
+
+    /// Colors of `preset` with the default layout.
+    pub fn preset(preset: ThemePreset) -> Theme {
+        let fg = |color| Style::default().fg(color);
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let layout = Theme {
+            item: Style::default(),
+            marked: bold,
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            path: Style::default().add_modifier(Modifier::UNDERLINED),
+            matched: Style::default().add_modifier(Modifier::UNDERLINED),
+            mark: bold,
+            branch: Style::default(),
+            dirty: bold,
+            ahead_behind: Style::default(),
+            stashes: Style::default(),
+            pending: Style::default(),
+            success: Style::default(),
+            error: bold,
+            list_width: 55,
+            filter_height: 10,
+            cheatsheet_height: 5,
+            item_height: 2,
+        };
+        match preset {
+            ThemePreset::NoColor => layout,
+            ThemePreset::Light => Theme {
+                item: fg(Color::Black).bg(Color::White),
+                marked: fg(Color::Black).bg(Color::LightYellow),
+                selected: Style::default()
+                    .bg(Color::LightGreen)
+                    .add_modifier(Modifier::BOLD),
+                path: fg(Color::LightCyan)
+                    .add_modifier(Modifier::UNDERLINED)
+                    .add_modifier(Modifier::ITALIC),
+                matched: fg(Color::Red).add_modifier(Modifier::BOLD),
+                mark: fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                branch: fg(Color::Magenta),
+                dirty: fg(Color::Red),
+                ahead_behind: fg(Color::Blue),
+                stashes: fg(Color::DarkGray),
+                pending: fg(Color::Gray),
+                success: fg(Color::Green),
+                error: fg(Color::Red),
+                ..layout
+            },
+            ThemePreset::Dark => Theme {
+                item: fg(Color::Gray),
+                marked: fg(Color::White).bg(Color::DarkGray),
+                selected: fg(Color::White)
+                    .bg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+                path: fg(Color::Cyan).add_modifier(Modifier::ITALIC),
+                matched: fg(Color::LightRed).add_modifier(Modifier::BOLD),
+                mark: fg(Color::LightMagenta).add_modifier(Modifier::BOLD),
+                branch: fg(Color::LightMagenta),
+                dirty: fg(Color::LightRed),
+                ahead_behind: fg(Color::LightBlue),
+                stashes: fg(Color::Gray),
+                pending: fg(Color::DarkGray),
+                success: fg(Color::LightGreen),
+                error: fg(Color::LightRed),
+                ..layout
+            },
+            ThemePreset::HighContrast => Theme {
+                item: fg(Color::White).bg(Color::Black),
+                marked: fg(Color::Black).bg(Color::Yellow),
+                selected: fg(Color::Black)
+                    .bg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+                path: Style::default().add_modifier(Modifier::UNDERLINED),
+                matched: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                branch: bold,
+                dirty: bold.fg(Color::LightRed),
+                ahead_behind: bold,
+                stashes: bold,
+                success: bold.fg(Color::LightGreen),
+                error: bold.fg(Color::LightRed),
+                ..layout
+            },
+        }
+    }
+}
+
+/// Whether the `NO_COLOR` environment variable asks for output without colors, see <https://no-color.org>.
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+#[test]
+fn no_color_unless_a_preset_is_set() {
+    let theme = |toml: &str, no_color| {
+        let config: ThemeConfig = toml::from_str(toml).unwrap();
+        Theme::new(Some(&config), no_color)
+    };
+    assert_eq!(Theme::preset(ThemePreset::Light), Theme::new(None, false));
+    assert_eq!(Theme::preset(ThemePreset::NoColor), Theme::new(None, true));
+    assert_eq!(
+        Theme::preset(ThemePreset::Dark),
+        theme(r#"preset = "Dark""#, true)
+    );
+    let one_line = theme("one_line = true\nlist_width = 150", false);
+    assert_eq!(1, one_line.item_height);
+    assert_eq!(100, one_line.list_width);
+    assert_eq!(Theme::preset(ThemePreset::Light).item, one_line.item);
+}
@@ -1,63 +1,107 @@
-use crate::WorkOrPersonal;
+use crate::bulk::{BulkOperation, BulkRun};
+use crate::clone::{
+    add_to_registry, alternate_directory, destination, inspect_destination, profile_for_url,
+    registry_entry, CloneDestination, CloneJob,
+};
+use crate::preview::{Preview, PreviewLoader};
+use crate::query::{Query, QueryError};
+use crate::remote::{canonical_url, rewrite_url, same_remote, RemoteUrl};
+use crate::status::{RepoStatus, StatusCache, StatusLoader};
+use crate::theme::{self, Theme};
+use crate::{logging, Action, Config, KeyMode, KeysConfig, SlipError, WorkOrPersonal};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 
 use super::repolist::*;
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::{
-    error::Error,
-    io,
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{self, Write},
+    path::PathBuf,
     time::{Duration, Instant},
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
 
 struct StatefulList {
     state: ListState,
     repolist: RepoList,
+    /// rows matching the query in the order they are shown, see `App::update_view`
+    view: Vec<Row>,
+    /// locations of the entries marked for bulk actions
+    marked: HashSet<String>,
 }
 
 impl StatefulList {
     fn with_items(repolist: RepoList) -> StatefulList {
         let mut state = ListState::default();
         state.select(Some(0));
-        StatefulList { state, repolist }
+        StatefulList {
+            state,
+            repolist,
+            view: vec![],
+            marked: HashSet::new(),
+        }
+    }
+
+    fn toggle_mark(&mut self, location: &str) {
+        if !self.marked.remove(location) {
+            self.marked.insert(location.to_string());
+        }
+    }
+
+    /// Entry of the row at `index` of the view.
+    fn entry(&self, index: usize) -> Option<Entry<'_>> {
+        self.view.get(index).map(|row| row.entry(&self.repolist))
+    }
+
+    fn selected(&self) -> Option<Entry<'_>> {
+        self.entry(self.state.selected()?)
+    }
+
+    /// Replaces the view, keeping the selection on the same row if it is still shown: statuses
+    /// arriving for a `dirty:` query move rows around while browsing.
+    fn set_view(&mut self, view: Vec<Row>) {
+        let selected = self.state.selected().unwrap_or(0);
+        let row = self.view.get(selected).copied();
+        self.view = view;
+        let index = row
+            .and_then(|row| self.view.iter().position(|shown| *shown == row))
+            .unwrap_or(selected.min(self.last()));
+        self.state.select(Some(index));
+    }
+
+    /// Index of the last row, 0 when nothing matches.
+    fn last(&self) -> usize {
+        self.view.len().saturating_sub(1)
     }
 
     fn next(&mut self) {
         let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.repolist.repos.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
+            Some(i) if i < self.last() => i + 1,
+            _ => 0,
         };
         self.state.select(Some(i));
     }
 
     fn previous(&mut self) {
         let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.repolist.repos.len() - 1
-                } else {
-                    i - 1
-                }
-            }
+            Some(0) => self.last(),
+            Some(i) => i - 1,
             None => 0,
         };
         self.state.select(Some(i));
@@ -69,13 +113,7 @@ impl StatefulList {
 
     fn go_ten_down(&mut self) {
         let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.repolist.repos.len() - 10 {
-                    self.repolist.repos.len() - 1
-                } else {
-                    i + 10
-                }
-            }
+            Some(i) => (i + 10).min(self.last()),
             None => 0,
         };
         self.state.select(Some(i));
@@ -83,20 +121,14 @@ impl StatefulList {
 
     fn go_ten_up(&mut self) {
         let i = match self.state.selected() {
-            Some(i) => {
-                if i < 10 {
-                    0
-                } else {
-                    i - 10
-                }
-            }
+            Some(i) => i.saturating_sub(10),
             None => 0,
         };
         self.state.select(Some(i));
     }
 
     fn end(&mut self) {
-        self.state.select(Some(self.repolist.repos.len() - 1));
+        self.state.select(Some(self.last()));
     }
 }
 
@@ -109,249 +141,2178 @@ impl StatefulList {
 struct App {
     items: StatefulList,
     search_text: String,
+    /// last query parsed from `search_text` without error
+    query: Query,
+    /// why `search_text` doesn't parse, shown in the search box
+    query_error: Option<QueryError>,
+    /// only repos of this profile are listed, all when `None`
     category: Option<WorkOrPersonal>,
+    /// the query, the profile filter or the registry changed since the view was computed
+    view_stale: bool,
+    loader: PreviewLoader,
+    /// previews by location, `None` while loading
+    previews: HashMap<String, Option<Preview>>,
+    status_loader: StatusLoader,
+    /// statuses by location, from the cache until collected again
+    statuses: HashMap<String, RepoStatus>,
+    /// locations whose status was collected in this run
+    refreshed: HashSet<String>,
+    actions: Vec<(Action, KeyBinding)>,
+    keymap: Keymap,
+    /// whether characters type into the search or run commands
+    key_mode: KeyMode,
+    config: Config,
+    theme: Theme,
+    mode: Mode,
+    /// shown instead of the preview until closed
+    panel: Option<Panel>,
+    /// inside of the list's borders and the first entry shown there, as last drawn
+    list_area: Rect,
+    list_offset: usize,
+    /// cheatsheet entries that can be clicked, as last drawn
+    cheatsheet: Vec<(Rect, Command)>,
+    /// time and entry of the last click, to tell double clicks
+    last_click: Option<(Instant, usize)>,
+    /// `enter` picks the selected path for the shell integration instead of running an action
+    pick_path: bool,
+    /// `post_clone` hooks run after cloning, unless `--no-hooks`
+    hooks: bool,
 }
 
 impl App {
-    fn new(repolist: RepoList, category: Option<WorkOrPersonal>) -> App {
+    fn new(
+        repolist: RepoList,
+        category: Option<WorkOrPersonal>,
+        mut actions: Vec<(Action, KeyBinding)>,
+        keymap: Keymap,
+        config: Config,
+        pick_path: bool,
+        hooks: bool,
+    ) -> App {
+        if pick_path {
+            actions.retain(|(_, binding)| binding.code != KeyCode::Enter);
+        }
+        let checkouts: Vec<(Repo, String)> = repolist
+            .repos
+            .iter()
+            .flat_map(|repo| {
+                std::iter::once(repo.location.clone())
+                    .chain(
+                        repo.worktrees
+                            .iter()
+                            .map(|worktree| worktree.location.clone()),
+                    )
+                    .map(move |location| (repo.clone(), location))
+            })
+            .collect();
+        let mut cache = StatusCache::load();
+        let statuses = checkouts
+            .iter()
+            .filter_map(|(_, location)| Some((location.clone(), cache.statuses.remove(location)?)))
+            .collect();
+        let key_mode = config
+            .ui
+            .as_ref()
+            .and_then(|ui| ui.keys.as_ref())
+            .and_then(|keys| keys.start)
+            .unwrap_or(KeyMode::Insert);
+        let theme = Theme::new(
+            config.ui.as_ref().and_then(|ui| ui.theme.as_ref()),
+            theme::no_color(),
+        );
         App {
+            status_loader: StatusLoader::start(checkouts),
+            statuses,
+            refreshed: HashSet::new(),
             items: StatefulList::with_items(repolist),
             search_text: String::new(),
+            query: Query::default(),
+            query_error: None,
+            category,
+            view_stale: true,
+            loader: PreviewLoader::start(),
+            previews: HashMap::new(),
+            actions,
+            keymap,
+            key_mode,
+            config,
+            theme,
+            mode: Mode::Browse,
+            panel: None,
+            list_area: Rect::default(),
+            list_offset: 0,
+            cheatsheet: vec![],
+            last_click: None,
+            pick_path,
+            hooks,
+        }
+    }
+
+    /// `action` (just the path if `None`) for the selected entry.
+    fn launch(&self, action: Option<Action>) -> Option<Launch> {
+        let selected = self.items.selected()?;
+        Some(Launch {
+            repo: selected.repo.clone(),
+            directory: selected.location().to_owned(),
+            action,
+        })
+    }
+
+    /// Repo and location of the marked entries in registry order, or of the selected one if none are.
+    fn targets(&self) -> Vec<(Repo, String)> {
+        if self.items.marked.is_empty() {
+            return self
+                .launch(None)
+                .map(|launch| (launch.repo, launch.directory))
+                .into_iter()
+                .collect();
+        }
+        self.items
+            .repolist
+            .repos
+            .iter()
+            .flat_map(|repo| {
+                std::iter::once(repo.location.as_str())
+                    .chain(
+                        repo.worktrees
+                            .iter()
+                            .map(|worktree| worktree.location.as_str()),
+                    )
+                    .filter(|location| self.items.marked.contains(*location))
+                    .map(move |location| (repo.clone(), location.to_string()))
+            })
+            .collect()
+    }
+
+    /// Locations of the repos of the targets, a marked worktree standing for its repo.
+    fn target_repos(&self) -> Vec<String> {
+        let mut locations: Vec<String> = vec![];
+        for (repo, _) in self.targets() {
+            if !locations.contains(&repo.location) {
+                locations.push(repo.location);
+            }
+        }
+        locations
+    }
+
+    fn start_bulk(&mut self, operation: BulkOperation) {
+        let targets = self.targets();
+        self.panel = Some(Panel::Bulk(BulkRun::start(
+            operation,
+            targets,
+            self.config.clone(),
+        )));
+    }
+
+    /// Adds the whitespace separated `tags` to the target repos and saves the registry.
+    fn tag_targets(&mut self, tags: &str) {
+        let tags: Vec<String> = tags.split_whitespace().map(str::to_string).collect();
+        let locations = self.target_repos();
+        let mut labels = vec![];
+        for location in &locations {
+            if let Some(repo) = self.items.repolist.find_mut(location) {
+                repo.add_tags(&tags);
+                labels.push((repo.name.clone(), repo.tags.join(" ")));
+            }
+        }
+        let saved = self.items.repolist.save_or_diff();
+        self.finish_registry_change(format!("tag {}", tags.join(" ")), labels, saved);
+    }
+
+    /// Takes the target repos off the registry, their files stay where they are.
+    fn remove_targets(&mut self) {
+        let locations = self.target_repos();
+        let mut labels = vec![];
+        self.items.repolist.repos.retain(|repo| {
+            let removed = locations.contains(&repo.location);
+            if removed {
+                labels.push((repo.name.clone(), "removed from the registry".to_string()));
+            }
+            !removed
+        });
+        let saved = self.items.repolist.save_or_diff();
+        self.items.marked.clear();
+        self.items.select_0();
+        self.finish_registry_change("remove".to_string(), labels, saved);
+    }
+
+    /// Shows the outcome of a registry change, or in a dry run the diff it would have made
+    /// and the registry as it is on disk again.
+    fn finish_registry_change(
+        &mut self,
+        title: String,
+        labels: Vec<(String, String)>,
+        saved: Result<Option<String>, SlipError>,
+    ) {
+        self.view_stale = true;
+        if let Ok(Some(diff)) = saved {
+            self.reload();
+            self.panel = Some(Panel::DryRun { title, diff });
+            return;
+        }
+        let results = labels
+            .into_iter()
+            .map(|(name, summary)| match &saved {
+                Ok(_) => (name, Ok(summary)),
+                Err(err) => (name, Err(err.to_string())),
+            })
+            .collect();
+        self.panel = Some(Panel::Bulk(BulkRun::finished(title, results)));
+    }
+
+    /// Adds the checkout at `directory` to the registry with the git identity of its profile.
+    fn register(
+        &mut self,
+        url: &str,
+        directory: PathBuf,
+        category: WorkOrPersonal,
+        title: &str,
+        summary: String,
+    ) -> Result<(), String> {
+        let (name, saved) = if crate::is_dry_run() {
+            // the identity is left alone, like everything else in a dry run
+            let repo =
+                registry_entry(url, directory, category, None).map_err(|err| err.to_string())?;
+            let name = repo.name.clone();
+            self.items.repolist.repos.push(repo);
+            (name, self.items.repolist.save_or_diff())
+        } else {
+            let repo = add_to_registry(url, directory, category, None, &self.config)
+                .map_err(|err| err.to_string())?;
+            self.reload();
+            (repo.name, Ok(None))
+        };
+        self.finish_registry_change(title.to_string(), vec![(name, summary)], saved);
+        Ok(())
+    }
+
+    /// The selected repo, not the worktree if one is selected.
+    fn selected_repo(&self) -> Option<Repo> {
+        self.launch(None).map(|launch| launch.repo)
+    }
+
+    /// Handles the submitted `input`, an error keeps its dialog open.
+    fn submit(&mut self, input: &Input) -> Result<(), String> {
+        let text = input.text.trim();
+        match input.kind {
+            InputKind::AddTags => {
+                if !text.is_empty() {
+                    self.tag_targets(text);
+                }
+            }
+            InputKind::EditTags => {
+                let repo = self.selected_repo().ok_or("no repo selected")?;
+                let tags = text.split_whitespace().map(str::to_string).collect();
+                self.edit_repo(&repo, "edit tags", |repo| {
+                    repo.tags = tags;
+                    Ok(repo.tags.join(" "))
+                })?;
+            }
+            InputKind::Rename => {
+                let repo = self.selected_repo().ok_or("no repo selected")?;
+                self.edit_repo(&repo, "rename", |repo| {
+                    let summary = format!("renamed from {}", repo.display_name());
+                    // an empty alias, or the name itself, lists the repo by its name again
+                    repo.alias = Some(text.to_string())
+                        .filter(|alias| !alias.is_empty() && *alias != repo.name);
+                    Ok(summary)
+                })?;
+            }
+            InputKind::Clone => {
+                let plan = self.clone_plan(text, input.category)?;
+                self.start_clone(plan)?;
+            }
+            InputKind::AddPath => {
+                let (directory, category) = self.add_plan(text, input.category)?;
+                self.register(
+                    "",
+                    directory,
+                    category,
+                    "add",
+                    format!("added to {category}"),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Changes the registered `repo` with `change` and saves the registry.
+    fn edit_repo(
+        &mut self,
+        repo: &Repo,
+        title: &str,
+        change: impl FnOnce(&mut Repo) -> Result<String, String>,
+    ) -> Result<(), String> {
+        let registered = self
+            .items
+            .repolist
+            .find_mut(&repo.location)
+            .ok_or_else(|| format!("{} isn't registered anymore", repo.location))?;
+        let summary = change(registered)?;
+        let name = registered.name.clone();
+        let saved = self.items.repolist.save_or_diff();
+        self.finish_registry_change(title.to_string(), vec![(name, summary)], saved);
+        Ok(())
+    }
+
+    /// Moves the selected repo to the other profile and gives it that profile's git identity.
+    fn change_category(&mut self) {
+        let Some(repo) = self.selected_repo() else {
+            return;
+        };
+        let category = other_category(repo.category);
+        let config = self.config.clone();
+        let changed = self.edit_repo(&repo, "change profile", |registered| {
+            // the registered entry is only changed once the identity is configured
+            let moved = Repo {
+                category,
+                ..registered.clone()
+            };
+            let identity = BulkOperation::Reconfig.run(&moved, &moved.location, &config)?;
+            *registered = moved;
+            Ok(format!("now {category}, {identity}"))
+        });
+        if let Err(err) = changed {
+            let results = vec![(repo.name, Err(err))];
+            self.panel = Some(Panel::Bulk(BulkRun::finished(
+                "change profile".to_string(),
+                results,
+            )));
+        }
+    }
+
+    /// Url, url to clone, directory and profile for cloning `url`, or why it can't be cloned.
+    fn clone_plan(&self, url: &str, category: Option<WorkOrPersonal>) -> Result<ClonePlan, String> {
+        if url.is_empty() {
+            return Err("paste the url to clone".to_string());
+        }
+        let category = category.unwrap_or_else(|| profile_for_url(&self.config, url));
+        let rewrites = self.config.rewrites(&category);
+        let url = canonical_url(url, rewrites);
+        if let Some(existing) = self
+            .items
+            .repolist
+            .repos
+            .iter()
+            .find(|repo| same_remote(&repo.url, &url))
+        {
+            return Err(format!("already cloned at {}", existing.location));
+        }
+        let directory =
+            destination(&url, None, category, &self.config).map_err(|err| err.to_string())?;
+        let (directory, existing) =
+            match inspect_destination(&url, rewrites, directory).map_err(|err| err.to_string())? {
+                CloneDestination::Empty(directory) => (directory, false),
+                CloneDestination::SameRepo(directory) => (directory, true),
+                CloneDestination::Occupied { directory, .. } => {
+                    (alternate_directory(&directory), false)
+                }
+            };
+        Ok(ClonePlan {
+            clone_url: rewrite_url(&url, rewrites),
+            url,
+            directory,
             category,
+            existing,
+        })
+    }
+
+    fn start_clone(&mut self, plan: ClonePlan) -> Result<(), String> {
+        if plan.existing {
+            // already cloned there, only missing from the registry
+            let summary = "existing clone added".to_string();
+            return self.register(&plan.url, plan.directory, plan.category, "clone", summary);
         }
+        self.panel = Some(Panel::Clone(Box::new(CloneJob::start(
+            plan.url,
+            plan.clone_url,
+            plan.directory,
+            plan.category,
+            self.config.clone(),
+            self.hooks,
+        ))));
+        Ok(())
     }
+
+    /// Works out where the text of a clone or add dialog leads, for the dialog to show.
+    fn plan_destination(&self, input: &mut Input) {
+        let text = input.text.trim();
+        input.destination = match input.kind {
+            InputKind::Clone if !text.is_empty() => Some(
+                self.clone_plan(text, input.category)
+                    .map(|plan| (plan.directory, plan.category)),
+            ),
+            InputKind::AddPath if !text.is_empty() => Some(self.add_plan(text, input.category)),
+            _ => None,
+        };
+    }
+
+    /// Directory and profile for adding the checkout at `path`, or why it can't be added.
+    fn add_plan(
+        &self,
+        path: &str,
+        category: Option<WorkOrPersonal>,
+    ) -> Result<(PathBuf, WorkOrPersonal), String> {
+        if path.is_empty() {
+            return Err("type the path of a checkout".to_string());
+        }
+        let directory = PathBuf::from(shellexpand::tilde(path).as_ref());
+        if !directory.is_dir() {
+            return Err(format!("{} is not a directory", directory.display()));
+        }
+        let location = directory.to_string_lossy();
+        if self.items.repolist.find(&location).is_some() {
+            return Err(format!("{location} is already registered"));
+        }
+        let category = category
+            .or(self.config.default)
+            .unwrap_or(WorkOrPersonal::Personal);
+        Ok((directory, category))
+    }
+
+    /// Reads the registry again, after repos were added.
+    fn reload(&mut self) {
+        match RepoList::get_config() {
+            Ok(repolist) => self.items.repolist = repolist,
+            Err(err) => tracing::warn!(%err, "can't reload the registry"),
+        }
+        self.view_stale = true;
+    }
+
+    /// Does `command`, the launches to leave the ui with if it ends it.
+    fn apply(&mut self, command: Command) -> Option<Vec<Launch>> {
+        match command {
+            Command::Exit if self.panel.is_some() => self.panel = None,
+            Command::Exit => return Some(vec![]),
+            Command::PickPath => return self.launch(None).map(|launch| vec![launch]),
+            Command::Run(action) => {
+                let launches: Vec<Launch> = self
+                    .targets()
+                    .into_iter()
+                    .map(|(repo, directory)| Launch {
+                        repo,
+                        directory,
+                        action: Some(action.clone()),
+                    })
+                    .collect();
+                return Some(launches).filter(|launches| !launches.is_empty());
+            }
+            Command::Top => self.items.select_0(),
+            Command::Bottom => self.items.end(),
+            Command::Next => self.items.next(),
+            Command::Previous => self.items.previous(),
+            Command::PageDown => self.items.go_ten_down(),
+            Command::PageUp => self.items.go_ten_up(),
+            Command::DeleteChar => {
+                self.search_text.pop();
+                self.update_query();
+            }
+            Command::ToggleCategory => self.toggle_category(),
+            Command::Mark => {
+                if let Some(launch) = self.launch(None) {
+                    self.items.toggle_mark(&launch.directory);
+                    self.items.next();
+                }
+            }
+            Command::BulkMenu => self.mode = Mode::BulkMenu,
+            Command::RepoMenu => self.mode = Mode::RepoMenu,
+            Command::Help => self.mode = Mode::Help,
+            Command::NormalMode => self.key_mode = KeyMode::Normal,
+            Command::InsertMode => self.key_mode = KeyMode::Insert,
+            Command::Bulk(operation) => self.start_bulk(operation),
+            Command::AddTags => {
+                self.mode = Mode::Input(Input::new(InputKind::AddTags, String::new()))
+            }
+            Command::Remove => self.mode = Mode::ConfirmRemove,
+        }
+        None
+    }
+
+    /// What a double click or `enter` does: pick the path or run the action bound to `enter`.
+    fn default_command(&self) -> Option<Command> {
+        if self.pick_path {
+            return Some(Command::PickPath);
+        }
+        self.actions
+            .iter()
+            .find(|(_, binding)| binding.code == KeyCode::Enter)
+            .or(self.actions.first())
+            .map(|(action, _)| Command::Run(action.clone()))
+    }
+
+    /// Scrolls with the wheel, selects with a click and runs the default command on a double click.
+    fn on_mouse(&mut self, mouse: MouseEvent) -> Option<Command> {
+        let len = self.items.view.len();
+        let selected = self.items.state.selected().unwrap_or(0);
+        match mouse.kind {
+            MouseEventKind::ScrollDown if len > 0 => {
+                self.items.state.select(Some((selected + 1).min(len - 1)))
+            }
+            MouseEventKind::ScrollUp => self.items.state.select(Some(selected.saturating_sub(1))),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let at = |area: &Rect| {
+                    (area.left()..area.right()).contains(&mouse.column)
+                        && (area.top()..area.bottom()).contains(&mouse.row)
+                };
+                if let Some((_, command)) = self.cheatsheet.iter().find(|(area, _)| at(area)) {
+                    return Some(command.clone());
+                }
+                if !at(&self.list_area) {
+                    return None;
+                }
+                let row = usize::from(mouse.row - self.list_area.top());
+                let index = self.list_offset + row / self.theme.item_height;
+                if index >= len {
+                    return None;
+                }
+                self.items.state.select(Some(index));
+                let now = Instant::now();
+                let double = self.last_click.is_some_and(|(at, clicked)| {
+                    clicked == index && now.duration_since(at) < DOUBLE_CLICK
+                });
+                if double {
+                    self.last_click = None;
+                    return self.default_command();
+                }
+                self.last_click = Some((now, index));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Takes the progress of the panel, refreshing the list once a clone is done.
+    fn update_panel(&mut self) {
+        match &mut self.panel {
+            Some(Panel::Bulk(bulk)) => bulk.update(),
+            Some(Panel::Clone(job)) => {
+                let running = job.result.is_none();
+                job.update();
+                if running && matches!(job.result, Some(Ok(_))) {
+                    self.reload();
+                }
+            }
+            Some(Panel::DryRun { .. }) | None => {}
+        }
+    }
+
+    /// Stores loaded previews and asks for the one of the selected entry if it isn't there yet.
+    fn update_previews(&mut self) {
+        while let Ok((location, preview)) = self.loader.results.try_recv() {
+            self.previews.insert(location, Some(preview));
+        }
+        let selected = self
+            .items
+            .selected()
+            .map(|entry| (entry.repo.clone(), entry.location().to_owned()));
+        if let Some((repo, location)) = selected {
+            if !self.previews.contains_key(&location) {
+                self.loader.request(&repo, &location);
+                self.previews.insert(location, None);
+            }
+        }
+    }
+
+    /// Stores the statuses collected since the last call.
+    fn update_statuses(&mut self) {
+        while let Ok((location, status)) = self.status_loader.results.try_recv() {
+            self.refreshed.insert(location.clone());
+            self.statuses.insert(location, status);
+            self.view_stale |= self.query.asks_dirty();
+        }
+    }
+
+    /// Keeps the statuses for the next run, a cache that can't be written only costs time.
+    fn save_statuses(self) {
+        let cache = StatusCache {
+            statuses: self.statuses.into_iter().collect(),
+        };
+        if let Err(err) = cache.save() {
+            tracing::warn!(%err, "can't save the status cache");
+        }
+    }
+
+    /// Parses the changed `search_text`, keeping the previous query if it has errors.
+    fn update_query(&mut self) {
+        match Query::parse(&self.search_text) {
+            Ok(query) => {
+                self.query = query;
+                self.query_error = None;
+            }
+            Err(err) => self.query_error = Some(err),
+        }
+        self.items.select_0();
+        self.view_stale = true;
+    }
+
+    /// Cycles the profile filter all -> work -> personal -> all.
+    fn toggle_category(&mut self) {
+        self.category = match self.category {
+            None => Some(WorkOrPersonal::Work),
+            Some(WorkOrPersonal::Work) => Some(WorkOrPersonal::Personal),
+            Some(WorkOrPersonal::Personal) => None,
+        };
+        self.items.select_0();
+        self.view_stale = true;
+    }
+
+    /// Matches and orders the registry again if something changed that affects the view,
+    /// not on every frame: scoring a large registry takes a while.
+    fn update_view(&mut self, matcher: &SkimMatcherV2) {
+        if !self.view_stale {
+            return;
+        }
+        self.view_stale = false;
+        let view = find_matches_in_order(
+            &self.items.repolist,
+            &self.query,
+            self.category,
+            matcher,
+            // only what the status loader found so far, repos match once their status arrives
+            &mut |repo| self.statuses.get(&repo.location).map(|status| status.dirty),
+        );
+        self.items.set_view(view);
+    }
+}
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Something a key or a click on the cheatsheet does while browsing the list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    /// leaves the ui, or closes the panel if one is shown
+    Exit,
+    PickPath,
+    Run(Action),
+    Top,
+    Bottom,
+    Next,
+    Previous,
+    PageDown,
+    PageUp,
+    DeleteChar,
+    ToggleCategory,
+    Mark,
+    BulkMenu,
+    RepoMenu,
+    /// shows every key of both modes and the actions
+    Help,
+    NormalMode,
+    InsertMode,
+    /// fetch, pull or reconfig the bulk targets, bound in the bulk menu
+    Bulk(BulkOperation),
+    AddTags,
+    Remove,
+}
+
+impl Command {
+    /// Commands keys can be bound to in `[ui.keys]`, in the order the cheatsheet lists them;
+    /// what doesn't fit is left to the help.
+    fn builtin() -> [Command; 21] {
+        [
+            Command::Exit,
+            Command::Help,
+            Command::NormalMode,
+            Command::InsertMode,
+            Command::Mark,
+            Command::BulkMenu,
+            Command::RepoMenu,
+            Command::ToggleCategory,
+            Command::Next,
+            Command::Previous,
+            Command::Top,
+            Command::Bottom,
+            Command::PageDown,
+            Command::PageUp,
+            Command::DeleteChar,
+            Command::PickPath,
+            Command::Bulk(BulkOperation::Fetch),
+            Command::Bulk(BulkOperation::Pull),
+            Command::Bulk(BulkOperation::Reconfig),
+            Command::AddTags,
+            Command::Remove,
+        ]
+    }
+
+    fn parse(name: &str) -> Option<Command> {
+        Command::builtin()
+            .into_iter()
+            .find(|command| command.name() == name)
+    }
+
+    /// Name in `[ui.keys]` and the cheatsheet, the action's for `Run`.
+    fn name(&self) -> &str {
+        match self {
+            Command::Exit => "exit",
+            Command::PickPath => "cd",
+            Command::Run(action) => &action.name,
+            Command::Top => "top",
+            Command::Bottom => "bottom",
+            Command::Next => "next",
+            Command::Previous => "previous",
+            Command::PageDown => "page-down",
+            Command::PageUp => "page-up",
+            Command::DeleteChar => "delete-char",
+            Command::ToggleCategory => "toggle-category",
+            Command::Mark => "mark",
+            Command::BulkMenu => "bulk-menu",
+            Command::RepoMenu => "repo-menu",
+            Command::Help => "help",
+            Command::NormalMode => "normal-mode",
+            Command::InsertMode => "insert-mode",
+            Command::Bulk(BulkOperation::Fetch) => "fetch",
+            Command::Bulk(BulkOperation::Pull) => "pull",
+            Command::Bulk(BulkOperation::Reconfig) => "reconfig",
+            Command::AddTags => "add-tags",
+            Command::Remove => "remove",
+        }
+    }
+
+    /// What it does, for the help.
+    fn description(&self) -> String {
+        match self {
+            Command::Exit => "leave, or close the panel".to_string(),
+            Command::PickPath => "leave and cd into the repo, with shell-init".to_string(),
+            Command::Run(action) => format!("run `{}`", action.command),
+            Command::Top => "select the first repo".to_string(),
+            Command::Bottom => "select the last repo".to_string(),
+            Command::Next => "select the next repo".to_string(),
+            Command::Previous => "select the previous repo".to_string(),
+            Command::PageDown => "go 10 repos down".to_string(),
+            Command::PageUp => "go 10 repos up".to_string(),
+            Command::DeleteChar => "delete the last character searched".to_string(),
+            Command::ToggleCategory => "list all, work or personal repos".to_string(),
+            Command::Mark => "mark for bulk actions and move on".to_string(),
+            Command::BulkMenu => "run something on the marked repos".to_string(),
+            Command::RepoMenu => "clone, add or edit repos".to_string(),
+            Command::Help => "show this".to_string(),
+            Command::NormalMode => "stop typing, keys are commands".to_string(),
+            Command::InsertMode => "type to search".to_string(),
+            Command::Bulk(operation) => operation.title().to_string(),
+            Command::AddTags => "add tags".to_string(),
+            Command::Remove => "remove from the registry".to_string(),
+        }
+    }
+}
+
+/// Keys bound to builtin commands while browsing in each mode and in the bulk menu: the defaults
+/// with `[ui.keys]` applied, each with the key as written for the cheatsheet.
+struct Keymap {
+    normal: Vec<(String, KeyBinding, Command)>,
+    insert: Vec<(String, KeyBinding, Command)>,
+    bulk: Vec<(String, KeyBinding, Command)>,
+}
+
+const INSERT_KEYS: &[(&str, &str)] = &[
+    ("esc", "exit"),
+    ("f1", "help"),
+    ("ctrl-o", "normal-mode"),
+    ("down", "next"),
+    ("tab", "next"),
+    ("up", "previous"),
+    ("backtab", "previous"),
+    ("left", "top"),
+    ("home", "top"),
+    ("right", "bottom"),
+    ("end", "bottom"),
+    ("pagedown", "page-down"),
+    ("pageup", "page-up"),
+    ("backspace", "delete-char"),
+    ("ctrl-space", "mark"),
+    ("ctrl-t", "toggle-category"),
+    ("ctrl-b", "bulk-menu"),
+    ("ctrl-n", "repo-menu"),
+];
+
+const NORMAL_KEYS: &[(&str, &str)] = &[
+    ("esc", "exit"),
+    ("q", "exit"),
+    ("?", "help"),
+    ("f1", "help"),
+    ("i", "insert-mode"),
+    ("/", "insert-mode"),
+    ("j", "next"),
+    ("down", "next"),
+    ("k", "previous"),
+    ("up", "previous"),
+    ("g", "top"),
+    ("home", "top"),
+    ("G", "bottom"),
+    ("end", "bottom"),
+    ("ctrl-d", "page-down"),
+    ("pagedown", "page-down"),
+    ("ctrl-u", "page-up"),
+    ("pageup", "page-up"),
+    ("backspace", "delete-char"),
+    ("space", "mark"),
+    ("t", "toggle-category"),
+    ("ctrl-t", "toggle-category"),
+    ("b", "bulk-menu"),
+    ("ctrl-b", "bulk-menu"),
+    ("n", "repo-menu"),
+    ("ctrl-n", "repo-menu"),
+];
+
+const BULK_KEYS: &[(&str, &str)] = &[
+    ("f", "fetch"),
+    ("p", "pull"),
+    ("i", "reconfig"),
+    ("t", "add-tags"),
+    ("r", "remove"),
+];
+
+impl Keymap {
+    fn new(keys: Option<&KeysConfig>) -> Result<Keymap, SlipError> {
+        let keys = keys.cloned().unwrap_or_default();
+        Ok(Keymap {
+            normal: bind_keys(NORMAL_KEYS, keys.normal.as_ref(), true)?,
+            insert: bind_keys(INSERT_KEYS, keys.insert.as_ref(), false)?,
+            bulk: bind_keys(BULK_KEYS, keys.bulk.as_ref(), true)?,
+        })
+    }
+
+    fn bindings(&self, mode: KeyMode) -> &[(String, KeyBinding, Command)] {
+        match mode {
+            KeyMode::Normal => &self.normal,
+            KeyMode::Insert => &self.insert,
+        }
+    }
+
+    /// Keys of `command` in `mode` as written, like `j,down`.
+    fn keys(&self, mode: KeyMode, command: &Command) -> String {
+        self.bindings(mode)
+            .iter()
+            .filter(|(_, _, bound)| bound == command)
+            .map(|(key, _, _)| key.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// `defaults` with the keys `configured` binds replacing theirs, `none` unbinding them. Plain
+/// characters are refused unless `chars`, in insert mode they type.
+fn bind_keys(
+    defaults: &[(&str, &str)],
+    configured: Option<&BTreeMap<String, String>>,
+    chars: bool,
+) -> Result<Vec<(String, KeyBinding, Command)>, SlipError> {
+    let configured = configured
+        .into_iter()
+        .flatten()
+        .map(|(key, name)| (key.as_str(), name.as_str()));
+    let mut bindings: Vec<(String, KeyBinding, Command)> = vec![];
+    for (key, name) in defaults.iter().copied().chain(configured) {
+        let binding = KeyBinding::parse_any(key).ok_or_else(|| {
+            SlipError::Usage(format!(
+                "`{key}` in [ui.keys] is not a key like j, enter, f2 or ctrl-e"
+            ))
+        })?;
+        if binding.is_char() && !chars {
+            return Err(SlipError::Usage(format!(
+                "`{key}` in [ui.keys.insert] types into the filter, bind a key like f2 or ctrl-e"
+            )));
+        }
+        bindings.retain(|(_, bound, _)| *bound != binding);
+        if name == "none" {
+            continue;
+        }
+        let command = Command::parse(name).ok_or_else(|| {
+            let names = Command::builtin().map(|command| command.name().to_string());
+            SlipError::Usage(format!(
+                "`{name}` bound to {key} in [ui.keys] is not one of none, {}",
+                names.join(", ")
+            ))
+        })?;
+        bindings.push((key.to_string(), binding, command));
+    }
+    Ok(bindings)
+}
+
+/// First entry the list shows with `selected` in view, for entries of `item_height` lines;
+/// `List` scrolls the same way but keeps it private.
+fn list_offset(
+    offset: usize,
+    selected: usize,
+    len: usize,
+    rows: usize,
+    item_height: usize,
+) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let visible = (rows / item_height).max(1);
+    let selected = selected.min(len - 1);
+    let offset = offset.min(len - 1);
+    if selected >= offset + visible {
+        selected + 1 - visible
+    } else {
+        offset.min(selected)
+    }
+}
+
+/// What keys do: browse the list, pick from a menu or type into a dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    /// the bulk action menu, opened with `ctrl-b`
+    BulkMenu,
+    /// clone, add and edit repos, opened with `ctrl-n`
+    RepoMenu,
+    Input(Input),
+    ConfirmRemove,
+    /// every key, opened with `?` in normal mode or `f1`
+    Help,
+}
+
+/// A dialog taking a line of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Input {
+    kind: InputKind,
+    text: String,
+    /// profile picked with `tab` for clones and added paths, `None` to decide from the config
+    category: Option<WorkOrPersonal>,
+    /// why the last submitted text was refused
+    error: Option<String>,
+    /// where the text of a clone or add dialog leads, worked out when the text or profile changes
+    destination: Option<Result<(PathBuf, WorkOrPersonal), String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputKind {
+    /// tags to add to the bulk targets
+    AddTags,
+    /// tags replacing those of the selected repo
+    EditTags,
+    /// alias the selected repo is listed under, `slip open` and `slip tag` still find it by name
+    Rename,
+    Clone,
+    AddPath,
+}
+
+impl Input {
+    fn new(kind: InputKind, text: String) -> Input {
+        Input {
+            kind,
+            text,
+            category: None,
+            error: None,
+            destination: None,
+        }
+    }
+}
+
+/// What the right pane shows instead of the preview.
+enum Panel {
+    Bulk(BulkRun),
+    Clone(Box<CloneJob>),
+    /// the registry diff a change would have made with `--dry-run`
+    DryRun {
+        title: String,
+        diff: String,
+    },
 }
 
+/// Where a url from the clone dialog goes.
+struct ClonePlan {
+    url: String,
+    clone_url: String,
+    directory: PathBuf,
+    category: WorkOrPersonal,
+    /// `directory` is already a clone of `url`
+    existing: bool,
+}
+
+fn other_category(category: WorkOrPersonal) -> WorkOrPersonal {
+    match category {
+        WorkOrPersonal::Work => WorkOrPersonal::Personal,
+        WorkOrPersonal::Personal => WorkOrPersonal::Work,
+    }
+}
+
+/// Key of an action, parsed from strings like `enter`, `f2`, `ctrl-e` or `alt-i`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// `None` for unknown keys and for plain characters, which type into the search box.
+    fn parse(key: &str) -> Option<KeyBinding> {
+        KeyBinding::parse_any(key).filter(|binding| !binding.is_char())
+    }
+
+    /// Like `parse`, also taking plain characters like `j` or `G` for normal mode.
+    fn parse_any(key: &str) -> Option<KeyBinding> {
+        let mut parts: Vec<&str> = key.split('-').collect();
+        let name = parts.pop()?;
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in parts {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                KeyCode::Char(c.to_ascii_lowercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name.to_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "insert" => KeyCode::Insert,
+                "delete" => KeyCode::Delete,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "esc" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(KeyBinding { code, modifiers })
+    }
+
+    /// A character without ctrl or alt, which types in insert mode.
+    fn is_char(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        match (self.code, key.code) {
+            // shift is part of the character already, it tells `G` from `g` without ctrl or alt
+            (KeyCode::Char(bound), KeyCode::Char(pressed)) => {
+                let same = if self.is_char() {
+                    bound == pressed
+                } else {
+                    bound.eq_ignore_ascii_case(&pressed)
+                };
+                same && self.modifiers - KeyModifiers::SHIFT == key.modifiers - KeyModifiers::SHIFT
+            }
+            (bound, pressed) => bound == pressed && self.modifiers == key.modifiers,
+        }
+    }
+}
+
+/// `actions` that have a key, with their parsed key.
+fn bind_actions(actions: Vec<Action>) -> Result<Vec<(Action, KeyBinding)>, SlipError> {
+    actions
+        .into_iter()
+        .filter_map(|action| {
+            let key = action.key.clone()?;
+            Some(match KeyBinding::parse(&key) {
+                Some(binding) => Ok((action, binding)),
+                None => Err(SlipError::Usage(format!(
+                    "key `{key}` of action {} is not a key like enter, f2 or ctrl-e",
+                    action.name
+                ))),
+            })
+        })
+        .collect()
+}
+
+/// Runs the ui until entries are picked, none when it is left with `Esc`.
+///
+/// With `pick_path`, `enter` picks the selected path instead of running the action bound to it.
+/// `draw_on_stderr` keeps stdout free for printing that path.
 pub fn main(
     category: Option<WorkOrPersonal>,
-) -> Result<std::option::Option<Launch>, Box<dyn Error>> {
+    config: &Config,
+    pick_path: bool,
+    draw_on_stderr: bool,
+    hooks: bool,
+) -> Result<Vec<Launch>, SlipError> {
+    let repolist = RepoList::get_config()?;
+    let actions = bind_actions(config.actions())?;
+    let keymap = Keymap::new(config.ui.as_ref().and_then(|ui| ui.keys.as_ref()))?;
+
     // setup terminal
+    logging::mute_stderr(true);
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let mut output: Box<dyn Write> = if draw_on_stderr {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    };
+    execute!(output, EnterAlternateScreen)?;
+    let mouse = config.mouse();
+    if mouse {
+        execute!(output, EnableMouseCapture)?;
+    }
+    let backend = CrosstermBackend::new(output);
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
     let tick_rate = Duration::from_millis(250);
-    let repolist = RepoList::get_config()?;
-    let app = App::new(repolist, category);
-    let res = run_app(&mut terminal, app, tick_rate);
+    let mut app = App::new(
+        repolist,
+        category,
+        actions,
+        keymap,
+        config.clone(),
+        pick_path,
+        hooks,
+    );
+    let res = run_app(&mut terminal, &mut app, tick_rate);
 
     // restore terminal
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    if mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    logging::mute_stderr(false);
     terminal.show_cursor()?;
+    app.save_statuses();
 
     Ok(res?)
 }
 
-pub enum LaunchType {
-    LaunchShell,
-    LaunchCode,
-}
-
+/// Action picked in the ui, for the checkout at `directory` of `repo`; one per marked entry.
 pub struct Launch {
+    pub repo: Repo,
     pub directory: String,
-    pub launch_type: LaunchType,
+    /// `None` when only the path was picked, see `pick_path` of `main`
+    pub action: Option<Action>,
 }
 
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
-    mut app: App,
+    app: &mut App,
     tick_rate: Duration,
-) -> io::Result<Option<Launch>> {
-    let last_tick = Instant::now();
+) -> io::Result<Vec<Launch>> {
+    let mut last_tick = Instant::now();
     let matcher = SkimMatcherV2::default();
     loop {
-        terminal.draw(|f| ui(f, &mut app, &matcher))?;
+        app.update_statuses();
+        app.update_view(&matcher);
+        app.update_previews();
+        app.update_panel();
+        terminal.draw(|f| ui(f, app, &matcher))?;
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
+        if last_tick.elapsed() >= tick_rate {
+            last_tick = Instant::now();
+        }
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Esc => return Ok(None),
-                    KeyCode::Left | KeyCode::Home => app.items.select_0(),
-                    KeyCode::Right | KeyCode::End => app.items.end(),
-                    KeyCode::Down | KeyCode::Tab => app.items.next(),
-                    KeyCode::Up | KeyCode::BackTab => app.items.previous(),
-                    KeyCode::PageDown => app.items.go_ten_down(),
-                    KeyCode::PageUp => app.items.go_ten_up(),
-                    KeyCode::Insert => {
-                        let index = app.items.state.selected();
-                        if let Some(index) = index {
-                            if let Some(selected) = find_matches_in_order(
-                                &app.items.repolist,
-                                &app.search_text,
-                                &matcher,
-                            )
-                            .into_iter()
-                            .nth(index)
-                            .map(|(repo, _)| repo)
-                            {
-                                return Ok(Some(Launch {
-                                    directory: selected.location.clone(),
-                                    launch_type: LaunchType::LaunchCode,
-                                }));
+            let event = event::read()?;
+            if let Event::Key(key) = event {
+                match app.mode.clone() {
+                    Mode::Browse => {}
+                    Mode::BulkMenu => {
+                        app.mode = Mode::Browse;
+                        let command = app
+                            .keymap
+                            .bulk
+                            .iter()
+                            .find(|(_, binding, _)| binding.matches(&key))
+                            .map(|(_, _, command)| command.clone());
+                        if let Some(command) = command {
+                            app.apply(command);
+                        }
+                        continue;
+                    }
+                    Mode::RepoMenu => {
+                        app.mode = Mode::Browse;
+                        let selected = app.selected_repo();
+                        let input = match (key.code, selected) {
+                            (KeyCode::Char('c'), _) => {
+                                Some(Input::new(InputKind::Clone, String::new()))
+                            }
+                            (KeyCode::Char('a'), _) => {
+                                Some(Input::new(InputKind::AddPath, String::new()))
+                            }
+                            (KeyCode::Char('t'), Some(repo)) => {
+                                Some(Input::new(InputKind::EditTags, repo.tags.join(" ")))
                             }
+                            (KeyCode::Char('n'), Some(repo)) => Some(Input::new(
+                                InputKind::Rename,
+                                repo.display_name().to_string(),
+                            )),
+                            (KeyCode::Char('p'), Some(_)) => {
+                                app.change_category();
+                                None
+                            }
+                            _ => None,
+                        };
+                        if let Some(input) = input {
+                            app.mode = Mode::Input(input);
                         }
-                        return Ok(None);
+                        continue;
                     }
-                    KeyCode::Enter => {
-                        let index = app.items.state.selected();
-                        if let Some(index) = index {
-                            if let Some(selected) = find_matches_in_order(
-                                &app.items.repolist,
-                                &app.search_text,
-                                &matcher,
-                            )
-                            .into_iter()
-                            .nth(index)
-                            .map(|(repo, _)| repo)
+                    Mode::Input(mut input) => {
+                        match key.code {
+                            KeyCode::Esc => app.mode = Mode::Browse,
+                            KeyCode::Enter => match app.submit(&input) {
+                                Ok(()) => app.mode = Mode::Browse,
+                                Err(err) => {
+                                    input.error = Some(err);
+                                    app.mode = Mode::Input(input);
+                                }
+                            },
+                            KeyCode::Tab
+                                if matches!(input.kind, InputKind::Clone | InputKind::AddPath) =>
+                            {
+                                input.category = match input.category {
+                                    None => Some(WorkOrPersonal::Work),
+                                    Some(WorkOrPersonal::Work) => Some(WorkOrPersonal::Personal),
+                                    Some(WorkOrPersonal::Personal) => None,
+                                };
+                                app.plan_destination(&mut input);
+                                app.mode = Mode::Input(input);
+                            }
+                            // ctrl and alt chords aren't text
+                            KeyCode::Char(c)
+                                if !key
+                                    .modifiers
+                                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
                             {
-                                return Ok(Some(Launch {
-                                    directory: selected.location.clone(),
-                                    launch_type: LaunchType::LaunchShell,
-                                }));
+                                input.text.push(c);
+                                input.error = None;
+                                app.plan_destination(&mut input);
+                                app.mode = Mode::Input(input);
                             }
+                            KeyCode::Backspace => {
+                                input.text.pop();
+                                input.error = None;
+                                app.plan_destination(&mut input);
+                                app.mode = Mode::Input(input);
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    Mode::Help => {
+                        app.mode = Mode::Browse;
+                        continue;
+                    }
+                    Mode::ConfirmRemove => {
+                        app.mode = Mode::Browse;
+                        if key.code == KeyCode::Char('y') {
+                            app.remove_targets();
                         }
-                        return Ok(None);
+                        continue;
                     }
-                    KeyCode::Char(a) => {
+                }
+                let bound = app
+                    .actions
+                    .iter()
+                    .find(|(_, binding)| binding.matches(&key))
+                    .map(|(action, _)| action.clone());
+                let insert = app.key_mode == KeyMode::Insert;
+                let command = match key.code {
+                    KeyCode::Enter if app.pick_path => Some(Command::PickPath),
+                    _ if bound.is_some() => bound.map(Command::Run),
+                    _ => app
+                        .keymap
+                        .bindings(app.key_mode)
+                        .iter()
+                        .find(|(_, binding, _)| binding.matches(&key))
+                        .map(|(_, _, command)| command.clone()),
+                };
+                let plain = !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+                match (&command, key.code) {
+                    (None, KeyCode::Char(a)) if insert && plain => {
                         app.search_text.push(a);
+                        app.update_query();
                         app.items.select_0();
                     }
-                    KeyCode::Backspace => {
-                        app.search_text.pop();
-                    }
                     _ => {}
                 }
+                if let Some(launches) = command.and_then(|command| app.apply(command)) {
+                    return Ok(launches);
+                }
+            } else if let Event::Mouse(mouse) = event {
+                if app.mode != Mode::Browse {
+                    continue;
+                }
+                let command = app.on_mouse(mouse);
+                if let Some(launches) = command.and_then(|command| app.apply(command)) {
+                    return Ok(launches);
+                }
             }
         }
     }
 }
 
-fn find_matches_in_order<'a>(
-    repolist: &'a RepoList,
-    search_text: &'a str,
+/// A row of the repo list by its indices in the registry, either a repo or one of its worktrees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Row {
+    repo: usize,
+    worktree: Option<usize>,
+}
+
+impl Row {
+    fn entry(self, repolist: &RepoList) -> Entry<'_> {
+        let repo = &repolist.repos[self.repo];
+        Entry {
+            repo,
+            worktree: self.worktree.map(|worktree| &repo.worktrees[worktree]),
+        }
+    }
+}
+
+/// A row of the repo list, either a repo or one of its worktrees.
+struct Entry<'a> {
+    repo: &'a Repo,
+    worktree: Option<&'a Worktree>,
+}
+
+impl Entry<'_> {
+    fn location(&self) -> &str {
+        match self.worktree {
+            Some(worktree) => &worktree.location,
+            None => &self.repo.location,
+        }
+    }
+}
+
+/// Repos of `category` (all if `None`) matching `query` ordered by score, each followed by its worktrees.
+fn find_matches_in_order(
+    repolist: &RepoList,
+    query: &Query,
+    category: Option<WorkOrPersonal>,
     matcher: &SkimMatcherV2,
-) -> Vec<(&'a Repo, i64)> {
+    dirty: &mut dyn FnMut(&Repo) -> Option<bool>,
+) -> Vec<Row> {
     let mut matched = repolist
         .repos
         .iter()
-        .map(|repo| {
-            let out = matcher.fuzzy_match(&repo.name, search_text);
-            (repo, out)
-        })
-        .filter(|x| x.1.is_some())
-        .map(|x| (x.0, x.1.unwrap()))
+        .enumerate()
+        .filter(|(_, repo)| category.is_none_or(|category| repo.category == category))
+        .filter_map(|(index, repo)| Some((index, repo, query.score(repo, matcher, dirty)?)))
         .collect::<Vec<_>>();
-    matched.sort_by(|a, b| b.1.cmp(&a.1));
+    matched.sort_by_key(|(_, _, score)| std::cmp::Reverse(*score));
     matched
+        .into_iter()
+        .flat_map(|(index, repo, _)| {
+            std::iter::once(Row {
+                repo: index,
+                worktree: None,
+            })
+            .chain((0..repo.worktrees.len()).map(move |worktree| Row {
+                repo: index,
+                worktree: Some(worktree),
+            }))
+        })
+        .collect()
+}
+
+/// `text` as spans in `style`, with the characters any of `terms` fuzzy matches highlighted.
+fn highlight(
+    text: String,
+    terms: &[&str],
+    matcher: &SkimMatcherV2,
+    style: Style,
+    matched_style: Style,
+) -> Vec<Span<'static>> {
+    let mut matched = vec![false; text.chars().count()];
+    for term in terms {
+        if let Some((_, indices)) = matcher.fuzzy_indices(&text, term) {
+            for index in indices {
+                matched[index] = true;
+            }
+        }
+    }
+    let highlighted = style.patch(matched_style);
+    let mut spans = vec![];
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (c, is_matched) in text.chars().zip(matched) {
+        if is_matched != run_matched && !run.is_empty() {
+            let style = if run_matched { highlighted } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(
+            run,
+            if run_matched { highlighted } else { style },
+        ));
+    }
+    spans
+}
+
+/// Compact status next to an entry like `main * ↑1 ↓2 ≡3`, dimmed while it comes from the cache.
+fn status_badges(status: &RepoStatus, refreshed: bool, theme: &Theme) -> Vec<Span<'static>> {
+    let style = |style: Style| {
+        if refreshed {
+            style
+        } else {
+            style.add_modifier(Modifier::DIM)
+        }
+    };
+    let mut badges = vec![Span::styled(
+        format!("  {}", status.branch.as_deref().unwrap_or("(detached)")),
+        style(theme.branch),
+    )];
+    if status.dirty {
+        badges.push(Span::styled(" *", style(theme.dirty)));
+    }
+    if status.ahead > 0 {
+        badges.push(Span::styled(
+            format!(" ↑{}", status.ahead),
+            style(theme.ahead_behind),
+        ));
+    }
+    if status.behind > 0 {
+        badges.push(Span::styled(
+            format!(" ↓{}", status.behind),
+            style(theme.ahead_behind),
+        ));
+    }
+    if status.stashes > 0 {
+        badges.push(Span::styled(
+            format!(" ≡{}", status.stashes),
+            style(theme.stashes),
+        ));
+    }
+    badges
+}
+
+/// Lines of the result panel of a bulk action.
+fn bulk_text(bulk: &BulkRun, theme: &Theme) -> Text<'static> {
+    let lines: Vec<Spans> = bulk
+        .results
+        .iter()
+        .map(|(label, result)| {
+            let (mark, style, summary) = match result {
+                None => ("…", theme.pending, String::new()),
+                Some(Ok(summary)) => ("✓", theme.success, summary.clone()),
+                Some(Err(err)) => ("✗", theme.error, err.clone()),
+            };
+            Spans::from(vec![
+                Span::styled(format!("{mark} "), style),
+                Span::styled(label.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(format!("  {summary}")),
+            ])
+        })
+        .collect();
+    Text::from(lines)
+}
+
+/// Lines of a menu, each a key and what it does.
+fn menu_text(entries: &[(&str, String)]) -> Text<'static> {
+    Text::from(
+        entries
+            .iter()
+            .map(|(key, name)| {
+                Spans::from(vec![
+                    Span::styled(
+                        key.to_string(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(format!("  {name}")),
+                ])
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Title and lines of the dialog of `input`: the text, where it leads and why it was refused.
+fn input_popup(app: &App, input: &Input, targets: &str) -> (String, Text<'static>) {
+    let title = match input.kind {
+        InputKind::AddTags => format!("Tags to add to {targets}"),
+        InputKind::EditTags => "Tags".to_string(),
+        InputKind::Rename => "Alias".to_string(),
+        InputKind::Clone => "Url to clone".to_string(),
+        InputKind::AddPath => "Path of the checkout to add".to_string(),
+    };
+    let mut lines = vec![Spans::from(vec![
+        Span::raw(input.text.clone()),
+        Span::styled("▏", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+    ])];
+    let destination = &input.destination;
+    if matches!(input.kind, InputKind::Clone | InputKind::AddPath) {
+        let profile = match (input.category, destination) {
+            (Some(category), _) => category.to_string(),
+            (None, Some(Ok((_, category)))) => format!("auto ({category})"),
+            (None, _) => "auto".to_string(),
+        };
+        lines.push(Spans::default());
+        lines.push(Spans::from(format!("profile: {profile}, Tab changes it")));
+    }
+    match (&input.error, destination) {
+        (Some(err), _) | (None, Some(Err(err))) => {
+            lines.push(Spans::from(Span::styled(err.clone(), app.theme.error)))
+        }
+        (None, Some(Ok((directory, _)))) => {
+            lines.push(Spans::from(format!("→ {}", directory.display())))
+        }
+        (None, None) => {}
+    }
+    lines.push(Spans::default());
+    lines.push(Spans::from("Enter applies, Esc cancels"));
+    (title, Text::from(lines))
+}
+
+/// Lines of the clone panel: git's progress and the hooks' output, then the outcome.
+fn clone_text(job: &CloneJob, theme: &Theme) -> Text<'static> {
+    let mut lines: Vec<Spans> = job
+        .lines
+        .iter()
+        .map(|line| Spans::from(line.clone()))
+        .collect();
+    match &job.result {
+        None => lines.push(Spans::from(Span::styled("cloning…", theme.pending))),
+        Some(Ok(repo)) => {
+            let outcome = if crate::is_dry_run() {
+                format!(
+                    "✓ dry run, {} would be added to {}",
+                    repo.name, repo.category
+                )
+            } else {
+                format!("✓ {} added to {}", repo.name, repo.category)
+            };
+            lines.push(Spans::from(Span::styled(outcome, theme.success)));
+            for failure in &job.hook_failures {
+                lines.push(Spans::from(Span::styled(failure.clone(), theme.error)));
+            }
+        }
+        Some(Err(err)) => lines.push(Spans::from(Span::styled(format!("✗ {err}"), theme.error))),
+    }
+    Text::from(lines)
+}
+
+/// Every command with its keys in both modes, then the actions.
+fn help_text(app: &App) -> Text<'static> {
+    let mut rows = vec![(
+        String::new(),
+        "insert".to_string(),
+        "normal".to_string(),
+        String::new(),
+    )];
+    for command in Command::builtin() {
+        let insert = app.keymap.keys(KeyMode::Insert, &command);
+        let normal = app.keymap.keys(KeyMode::Normal, &command);
+        if !insert.is_empty() || !normal.is_empty() {
+            let name = command.name().to_string();
+            rows.push((name, insert, normal, command.description()));
+        }
+    }
+    let mut actions = vec![];
+    if app.pick_path {
+        actions.push(("enter".to_string(), Command::PickPath));
+    }
+    for (action, _) in &app.actions {
+        let key = action.key.clone().unwrap_or_default();
+        actions.push((key, Command::Run(action.clone())));
+    }
+    for (key, command) in actions {
+        let name = command.name().to_string();
+        rows.push((name, key.clone(), key, command.description()));
+    }
+    let width = |column: fn(&(String, String, String, String)) -> &String| {
+        rows.iter().map(|row| column(row).len()).max().unwrap_or(0) + 2
+    };
+    let (name_width, insert_width, normal_width) = (
+        width(|row| &row.0),
+        width(|row| &row.1),
+        width(|row| &row.2),
+    );
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines: Vec<Spans> = rows
+        .iter()
+        .map(|(name, insert, normal, description)| {
+            Spans::from(vec![
+                Span::styled(format!("{name:<name_width$}"), bold),
+                Span::raw(format!(
+                    "{insert:<insert_width$}{normal:<normal_width$}{description}"
+                )),
+            ])
+        })
+        .collect();
+    lines.push(Spans::default());
+    let bulk: Vec<String> = app
+        .keymap
+        .bulk
+        .iter()
+        .map(|(key, _, command)| format!("{key} {}", command.name()))
+        .collect();
+    lines.push(Spans::from(format!("bulk menu: {}", bulk.join(", "))));
+    lines.push(Spans::from(
+        "in insert mode characters type into the filter",
+    ));
+    Text::from(lines)
+}
+
+/// Lines of a registry diff, added ones in the success style and removed ones in the error style.
+fn diff_text(diff: &str, theme: &Theme) -> Text<'static> {
+    Text::from(
+        diff.lines()
+            .map(|line| {
+                let style = match line.chars().next() {
+                    Some('+') => theme.success,
+                    Some('-') => theme.error,
+                    _ => Style::default(),
+                };
+                Spans::from(Span::styled(line.to_string(), style))
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Area of `width` percent of `area` and `height` lines in its middle.
+fn centered(width: u16, height: u16, area: Rect) -> Rect {
+    // in u32, wide terminals overflow u16 before dividing
+    let width = (u32::from(area.width) * u32::from(width.min(100)) / 100) as u16;
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Lines of the preview pane.
+fn preview_text(preview: &Preview) -> Text<'static> {
+    let heading = |text: &str| {
+        Spans::from(Span::styled(
+            text.to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+    };
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::styled("branch ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(preview.status.to_string()),
+        ]),
+        Spans::from(vec![
+            Span::styled("identity ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(
+                preview
+                    .identity
+                    .clone()
+                    .unwrap_or_else(|| "(not set)".to_string()),
+            ),
+        ]),
+        Spans::default(),
+        heading("remotes"),
+    ];
+    lines.extend(
+        preview
+            .remotes
+            .iter()
+            .map(|(name, url)| Spans::from(format!("  {name}  {url}"))),
+    );
+    lines.push(Spans::default());
+    lines.push(heading("commits"));
+    lines.extend(
+        preview
+            .commits
+            .iter()
+            .map(|commit| Spans::from(format!("  {commit}"))),
+    );
+    if !preview.readme.is_empty() {
+        lines.push(Spans::default());
+        lines.push(heading("README"));
+        lines.extend(
+            preview
+                .readme
+                .iter()
+                .map(|line| Spans::from(format!("  {line}"))),
+        );
+    }
+    Text::from(lines)
 }
 
 fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App, matcher: &SkimMatcherV2) {
-    // Create two chunks with equal horizontal screen space
+    let theme = app.theme.clone();
+    // filter on top, list and preview in the middle, cheatsheet at the bottom, sized by the theme
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Percentage(10),
-                Constraint::Percentage(85),
-                Constraint::Percentage(5),
+                Constraint::Percentage(theme.filter_height),
+                Constraint::Percentage(100 - theme.filter_height - theme.cheatsheet_height),
+                Constraint::Percentage(theme.cheatsheet_height),
             ]
             .as_ref(),
         )
         .split(f.size());
 
-    let input = Paragraph::new(app.search_text.as_ref())
-        .block(Block::default().borders(Borders::ALL).title("Filter repos"));
+    let input_block = match &app.query_error {
+        Some(err) => Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled(format!("Filter repos: {err}"), theme.error))
+            .border_style(theme.error),
+        None => Block::default()
+            .borders(Borders::ALL)
+            .title(match app.key_mode {
+                KeyMode::Insert => "Filter repos",
+                KeyMode::Normal => "Filter repos [normal]",
+            }),
+    };
+    let input = Paragraph::new(app.search_text.as_ref()).block(input_block);
     f.render_widget(input, chunks[0]);
 
-    // Iterate through all elements in the `items` app and append some debug text to it.
-    let items: Vec<ListItem> =
-        find_matches_in_order(&app.items.repolist, &app.search_text, matcher)
-            .into_iter()
-            .map(|(repo, _order)| {
-                let mut lines = vec![Spans::from(Span::styled(
-                    format!("{} {}  {}", _order, repo.name, repo.category),
-                    Style::default().add_modifier(Modifier::BOLD),
-                ))];
-                lines.push(Spans::from(Span::styled(
-                    repo.location.clone(),
-                    Style::default()
-                        .fg(Color::LightCyan)
-                        .add_modifier(Modifier::UNDERLINED)
-                        .add_modifier(Modifier::ITALIC),
-                )));
-                ListItem::new(lines).style(Style::default().fg(Color::Black).bg(Color::White))
-            })
-            .collect();
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(theme.list_width),
+                Constraint::Percentage(100 - theme.list_width),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[1]);
+
+    // Only the rows in view are drawn, a large registry would take a while otherwise.
+    app.list_area = Block::default().borders(Borders::ALL).inner(panes[0]);
+    let rows = usize::from(app.list_area.height);
+    let selected = app.items.state.selected().unwrap_or(0);
+    app.list_offset = list_offset(
+        app.list_offset,
+        selected,
+        app.items.view.len(),
+        rows,
+        theme.item_height,
+    );
+    let shown = app.list_offset
+        ..app
+            .items
+            .view
+            .len()
+            .min(app.list_offset + (rows / theme.item_height).max(1));
+    let mut state = ListState::default();
+    state.select(Some(selected.saturating_sub(app.list_offset)).filter(|_| !shown.is_empty()));
+
+    // the repos and worktrees in view: matches highlighted, then status badges, mark and path
+    let terms = app.query.text_terms();
+    let selected_location = app
+        .items
+        .selected()
+        .map(|entry| entry.location().to_owned());
+    let items: Vec<ListItem> = shown
+        .filter_map(|index| app.items.entry(index))
+        .map(|entry| {
+            let bold = Style::default().add_modifier(Modifier::BOLD);
+            let mut title = match entry.worktree {
+                Some(worktree) => {
+                    let branch = format!("    └ {}", worktree.branch);
+                    highlight(branch, &terms, matcher, bold, theme.matched)
+                }
+                None => {
+                    let repo = entry.repo;
+                    let name = repo.display_name().to_string();
+                    let mut title = highlight(name, &terms, matcher, bold, theme.matched);
+                    if let Some(remote) = RemoteUrl::parse(&repo.url) {
+                        title.push(Span::raw("  "));
+                        title.extend(highlight(
+                            remote.host.clone(),
+                            &terms,
+                            matcher,
+                            Style::default(),
+                            theme.matched,
+                        ));
+                        if let Some(owner) = remote.owner() {
+                            title.push(Span::raw("/"));
+                            title.extend(highlight(
+                                owner.to_string(),
+                                &terms,
+                                matcher,
+                                Style::default(),
+                                theme.matched,
+                            ));
+                        }
+                    }
+                    title.push(Span::raw(format!("  {}", repo.category)));
+                    for tag in &repo.tags {
+                        title.push(Span::raw("  #"));
+                        title.extend(highlight(
+                            tag.clone(),
+                            &terms,
+                            matcher,
+                            Style::default(),
+                            theme.matched,
+                        ));
+                    }
+                    title
+                }
+            };
+            if let Some(status) = app.statuses.get(entry.location()) {
+                title.extend(status_badges(
+                    status,
+                    app.refreshed.contains(entry.location()),
+                    &theme,
+                ));
+            }
+            let marked = app.items.marked.contains(entry.location());
+            if marked {
+                title.insert(0, Span::styled("● ", theme.mark));
+            }
+            let path = highlight(
+                entry.location().to_owned(),
+                &terms,
+                matcher,
+                theme.path,
+                theme.matched,
+            );
+            let lines = if theme.item_height == 1 {
+                title.push(Span::raw("  "));
+                title.extend(path);
+                vec![Spans::from(title)]
+            } else {
+                vec![Spans::from(title), Spans::from(path)]
+            };
+            ListItem::new(lines).style(if marked { theme.marked } else { theme.item })
+        })
+        .collect();
 
     // Create a List from all list items and highlight the currently selected one
     let items = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Repos"))
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
-        )
+        .block(Block::default().borders(Borders::ALL).title(
+            match (app.category, app.items.marked.len()) {
+                (Some(category), 0) => format!("Repos ({category})"),
+                (None, 0) => "Repos (all)".to_string(),
+                (Some(category), marked) => format!("Repos ({category}, {marked} marked)"),
+                (None, marked) => format!("Repos (all, {marked} marked)"),
+            },
+        ))
+        .highlight_style(theme.selected)
         .highlight_symbol(">> ");
 
     // We can now render the item list
-    f.render_stateful_widget(items, chunks[1], &mut app.items.state);
-    // Let's do the same for the events.
-    // The event list doesn't have any state and only displays the current state of the list.
+    f.render_stateful_widget(items, panes[0], &mut state);
+
+    if let Some(panel) = &app.panel {
+        let (title, text) = match panel {
+            Panel::Bulk(bulk) => (
+                format!(
+                    "{} ({}/{}), Esc closes",
+                    bulk.title,
+                    bulk.completed(),
+                    bulk.results.len()
+                ),
+                bulk_text(bulk, &theme),
+            ),
+            Panel::Clone(job) => (
+                format!("clone {}, Esc closes", job.url),
+                clone_text(job, &theme),
+            ),
+            Panel::DryRun { title, diff } => (
+                format!("{title}, dry run, registry unchanged, Esc closes"),
+                diff_text(diff, &theme),
+            ),
+        };
+        let results = Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .wrap(Wrap { trim: false });
+        f.render_widget(results, panes[1]);
+    } else {
+        let preview = match selected_location
+            .as_ref()
+            .map(|location| app.previews.get(location))
+        {
+            Some(Some(Some(preview))) => preview_text(preview),
+            Some(_) => Text::raw("loading…"),
+            None => Text::raw(""),
+        };
+        let preview = Paragraph::new(preview)
+            .block(Block::default().borders(Borders::ALL).title("Preview"))
+            .wrap(Wrap { trim: false });
+        f.render_widget(preview, panes[1]);
+    }
+    let targets = match app.items.marked.len() {
+        0 => "the selected repo".to_string(),
+        marked => format!("{marked} marked"),
+    };
+    let popup = match &app.mode {
+        Mode::Browse => None,
+        Mode::BulkMenu => {
+            let entries: Vec<(&str, String)> = app
+                .keymap
+                .bulk
+                .iter()
+                .map(|(key, _, command)| (key.as_str(), command.description()))
+                .collect();
+            Some((format!("Run on {targets}"), menu_text(&entries)))
+        }
+        Mode::RepoMenu => {
+            let mut entries = vec![
+                ("c", "clone a url".to_string()),
+                ("a", "add an existing checkout".to_string()),
+            ];
+            if let Some(repo) = app.selected_repo() {
+                let name = repo.display_name();
+                entries.push(("t", format!("edit the tags of {name}")));
+                entries.push(("n", format!("rename {name} in the list")));
+                entries.push((
+                    "p",
+                    format!("move {name} to {}", other_category(repo.category)),
+                ));
+            }
+            Some(("Repos".to_string(), menu_text(&entries)))
+        }
+        Mode::Input(input) => Some(input_popup(app, input, &targets)),
+        Mode::ConfirmRemove => Some((
+            "Remove from the registry".to_string(),
+            Text::raw(format!(
+                "Take {targets} off the registry? Files are kept. y/n"
+            )),
+        )),
+        Mode::Help => Some(("Keys, any key closes".to_string(), help_text(app))),
+    };
+    if let Some((title, text)) = popup {
+        let area = match app.mode {
+            Mode::Help => centered(80, text.height() as u16 + 2, f.size()),
+            _ => centered(50, 9, f.size()),
+        };
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(text)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+    // The cheatsheet lists the keys of the mode, entries wrap and can be clicked.
     let bold = Style::default().add_modifier(Modifier::BOLD);
-    let cheatsheet = Paragraph::new(Text::from(vec![
-        Spans::from(vec![
-            //Esc
-            Span::styled("E", bold),
-            Span::from("sc --> exit        "),
-            // Enter
-            Span::styled("E", bold),
-            Span::from("nter --> change directory and shell    "),
-            // Insert
-            Span::styled("I", bold),
-            Span::from("nsert --> open in vscode       "),
-            // Any char
-            Span::styled("A", bold),
-            Span::from("ny char --> to search      "),
-        ]),
-        Spans::from(vec![
-            // left
-            Span::styled("L", bold),
-            Span::from("eft --> go to top  "),
-            // down
-            Span::styled("D", bold),
-            Span::from("own --> next                           "),
-            // up
-            Span::styled("U", bold),
-            Span::from("p --> previous                 "),
-            // backspace
-            Span::styled("B", bold),
-            Span::from("ackspace --> delete last character "),
-        ]),
-        // Spans::from(vec![
-        //     // left
-        //     Span::styled("P", bold),
-        //     Span::from("age Down --> current + 10"),
-        //     // down
-        //     Span::styled("P", bold),
-        //     Span::from("age Up --> current - 10"),
-        //     // up
-        //     Span::styled("H", bold),
-        //     Span::from("ome --> get to top"),
-        //     // backspace
-        //     Span::styled("E", bold),
-        //     Span::from("end --> get to bottom "),
-        // ]),
-    ]));
-    f.render_widget(cheatsheet, chunks[2]);
+    let mut entries = vec![];
+    if app.pick_path {
+        entries.push(("enter".to_string(), Some(Command::PickPath)));
+    }
+    for (action, _) in &app.actions {
+        let key = action.key.clone().unwrap_or_default();
+        entries.push((key, Some(Command::Run(action.clone()))));
+    }
+    for command in Command::builtin() {
+        let keys = app.keymap.keys(app.key_mode, &command);
+        if !keys.is_empty() {
+            entries.push((keys, Some(command)));
+        }
+    }
+    if app.key_mode == KeyMode::Insert {
+        entries.push(("any char".to_string(), None));
+    }
+    app.cheatsheet.clear();
+    let area = chunks[2];
+    let mut lines = vec![Spans::default()];
+    let (mut x, mut y) = (area.x, area.y);
+    for (key, command) in entries {
+        let name = command.as_ref().map_or("search", Command::name);
+        let spans = vec![Span::styled(key, bold), Span::raw(format!(" {name}  "))];
+        let width = spans.iter().map(Span::width).sum::<usize>() as u16;
+        if x > area.x && x + width > area.right() {
+            lines.push(Spans::default());
+            (x, y) = (area.x, y + 1);
+        }
+        if let Some(command) = command {
+            let width = width.min(area.right().saturating_sub(x));
+            app.cheatsheet.push((Rect::new(x, y, width, 1), command));
+        }
+        x += width;
+        lines.last_mut().unwrap().0.extend(spans);
+    }
+    app.cheatsheet.retain(|(entry, _)| entry.y < area.bottom());
+    let cheatsheet = Paragraph::new(Text::from(lines));
+    f.render_widget(cheatsheet, area);
+}
+
+#[test]
+fn filter_by_category() {
+    let repo = |name: &str, category| Repo {
+        category,
+        ..Repo::for_test(name, &format!("/src/{name}"))
+    };
+    let repolist = RepoList {
+        repos: vec![
+            repo("api", WorkOrPersonal::Work),
+            repo("dotfiles", WorkOrPersonal::Personal),
+        ],
+    };
+    let matcher = SkimMatcherV2::default();
+    let names = |category| {
+        find_matches_in_order(
+            &repolist,
+            &Query::default(),
+            category,
+            &matcher,
+            &mut |_| Some(false),
+        )
+        .iter()
+        .map(|row| row.entry(&repolist).repo.name.clone())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["api", "dotfiles"], names(None));
+    assert_eq!(vec!["api"], names(Some(WorkOrPersonal::Work)));
+    assert_eq!(vec!["dotfiles"], names(Some(WorkOrPersonal::Personal)));
+}
+
+#[test]
+fn search_across_fields() {
+    let repo = |url: &str, tags: &[&str]| Repo {
+        url: url.to_string(),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..Repo::for_test("api", &format!("/src/{}", url.rsplit(':').next().unwrap()))
+    };
+    let repolist = RepoList {
+        repos: vec![
+            repo("git@github.com:globex/api.git", &["billing"]),
+            repo("git@gitlab.com:acme/api.git", &[]),
+        ],
+    };
+    let matcher = SkimMatcherV2::default();
+    let urls = |search_text| {
+        let query = Query::parse(search_text).unwrap();
+        find_matches_in_order(&repolist, &query, None, &matcher, &mut |_| Some(false))
+            .iter()
+            .map(|row| row.entry(&repolist).repo.url.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["git@gitlab.com:acme/api.git"], urls("acme api"));
+    assert_eq!(vec!["git@gitlab.com:acme/api.git"], urls("gitlab"));
+    assert_eq!(vec!["git@github.com:globex/api.git"], urls("billing"));
+    assert!(urls("api nothing-like-this").is_empty());
+}
+
+#[test]
+fn parse_key_bindings() {
+    let key = |code, modifiers| Some(KeyBinding { code, modifiers });
+    assert_eq!(
+        key(KeyCode::Enter, KeyModifiers::NONE),
+        KeyBinding::parse("enter")
+    );
+    assert_eq!(
+        key(KeyCode::F(2), KeyModifiers::NONE),
+        KeyBinding::parse("F2")
+    );
+    assert_eq!(
+        key(KeyCode::Char('e'), KeyModifiers::CONTROL),
+        KeyBinding::parse("ctrl-e")
+    );
+    assert_eq!(
+        key(KeyCode::Insert, KeyModifiers::ALT | KeyModifiers::SHIFT),
+        KeyBinding::parse("alt-shift-insert")
+    );
+    assert_eq!(None, KeyBinding::parse("e"));
+    assert_eq!(None, KeyBinding::parse("hyper-e"));
+    assert_eq!(None, KeyBinding::parse("fx"));
+
+    let binding = KeyBinding::parse("ctrl-e").unwrap();
+    assert!(binding.matches(&KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)));
+    assert!(!binding.matches(&KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE)));
+
+    let binding = KeyBinding::parse_any("G").unwrap();
+    assert!(binding.matches(&KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT)));
+    assert!(!binding.matches(&KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE)));
+    assert_eq!(
+        key(KeyCode::Char(' '), KeyModifiers::NONE),
+        KeyBinding::parse_any("space")
+    );
+}
+
+#[test]
+fn keymap_from_config() {
+    let keys: KeysConfig = toml::from_str(
+        r#"
+        [normal]
+        x = "mark"
+        space = "none"
+        "ctrl-j" = "next"
+        "#,
+    )
+    .unwrap();
+    let keymap = Keymap::new(Some(&keys)).unwrap();
+    assert_eq!("x", keymap.keys(KeyMode::Normal, &Command::Mark));
+    assert_eq!(
+        "j,down,ctrl-j",
+        keymap.keys(KeyMode::Normal, &Command::Next)
+    );
+    assert_eq!("ctrl-space", keymap.keys(KeyMode::Insert, &Command::Mark));
+
+    let keys: KeysConfig = toml::from_str("[bulk]\nf = \"none\"\nF = \"fetch\"").unwrap();
+    let keymap = Keymap::new(Some(&keys)).unwrap();
+    let fetch = KeyEvent::new(KeyCode::Char('F'), KeyModifiers::SHIFT);
+    assert!(keymap
+        .bulk
+        .iter()
+        .any(|(_, binding, command)| binding.matches(&fetch)
+            && *command == Command::Bulk(BulkOperation::Fetch)));
+    assert_eq!(5, keymap.bulk.len());
+
+    let keys: KeysConfig = toml::from_str("[insert]\nesc = \"jump\"").unwrap();
+    assert!(Keymap::new(Some(&keys)).is_err());
+    // plain characters type in insert mode
+    let keys: KeysConfig = toml::from_str("[insert]\nx = \"mark\"").unwrap();
+    assert!(Keymap::new(Some(&keys)).is_err());
+}
+
+#[test]
+fn center_popups_on_wide_terminals() {
+    let area = Rect::new(0, 0, 1000, 50);
+    assert_eq!(Rect::new(100, 10, 800, 30), centered(80, 30, area));
+    assert_eq!(Rect::new(0, 0, 1000, 50), centered(100, 80, area));
+}
+
+#[test]
+fn scroll_like_list() {
+    // 3 entries of 2 lines fit into 7 rows
+    assert_eq!(0, list_offset(0, 2, 10, 7, 2));
+    assert_eq!(1, list_offset(0, 3, 10, 7, 2));
+    assert_eq!(1, list_offset(1, 2, 10, 7, 2));
+    assert_eq!(2, list_offset(4, 2, 10, 7, 2));
+    // the list shrank below the offset
+    assert_eq!(1, list_offset(8, 1, 2, 7, 2));
+    assert_eq!(0, list_offset(3, 0, 0, 7, 2));
+    // 7 entries of 1 line
+    assert_eq!(2, list_offset(0, 8, 10, 7, 1));
+}
+
+#[test]
+fn navigate_the_filtered_view() {
+    let repo = |name: &str| Repo::for_test(name, &format!("/src/{name}"));
+    let mut list = StatefulList::with_items(RepoList {
+        repos: vec![repo("api"), repo("web"), repo("api-docs")],
+    });
+    let query = Query::parse("api").unwrap();
+    let matcher = SkimMatcherV2::default();
+    list.view = find_matches_in_order(&list.repolist, &query, None, &matcher, &mut |_| Some(false));
+    // fewer than 10 rows, and fewer rows than repos
+    list.go_ten_down();
+    assert_eq!(Some(1), list.state.selected());
+    list.next();
+    assert_eq!(Some(0), list.state.selected());
+    list.previous();
+    list.end();
+    assert_eq!("api-docs", list.selected().unwrap().repo.name);
+    // the selection stays on its row when the view changes around it
+    let all = Query::parse("").unwrap();
+    list.set_view(find_matches_in_order(
+        &list.repolist,
+        &all,
+        None,
+        &matcher,
+        &mut |_| None,
+    ));
+    assert_eq!(Some(2), list.state.selected());
+    assert_eq!("api-docs", list.selected().unwrap().repo.name);
+
+    list.view.clear();
+    list.end();
+    list.go_ten_down();
+    list.previous();
+    assert_eq!(Some(0), list.state.selected());
+    assert!(list.selected().is_none());
 }